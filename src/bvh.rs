@@ -0,0 +1,148 @@
+use crate::triangles::*;
+
+// the most triangles a leaf holds before it gets split
+const MAX_LEAF_SIZE: usize = 4;
+
+enum BvhNode {
+    Leaf { aabb: Aabb, start: usize, count: usize },
+    Branch { aabb: Aabb, left: usize, right: usize },
+}
+
+impl BvhNode {
+    fn aabb(&self) -> Aabb {
+        match self {
+            BvhNode::Leaf { aabb, .. } | BvhNode::Branch { aabb, .. } => *aabb,
+        }
+    }
+}
+
+// bounding volume hierarchy over an object's (local space) triangles, used to
+// skip whole groups of triangles that can't be on screen
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    // triangle indices, ordered so every leaf owns a contiguous run of them
+    indices: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn build(triangles: &[ColorTriangle]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            indices: (0..triangles.len()).collect(),
+        };
+
+        if !triangles.is_empty() {
+            bvh.build_node(triangles, 0, triangles.len());
+        }
+
+        bvh
+    }
+
+    // builds the node for indices[start..end] and returns its index
+    fn build_node(&mut self, triangles: &[ColorTriangle], start: usize, end: usize) -> usize {
+        let aabbs = self.indices[start..end]
+            .iter()
+            .map(|&i| triangles[i].tri.aabb())
+            .collect::<Vec<Aabb>>();
+        let aabb = aabbs[1..].iter().fold(aabbs[0], |a, &b| a.merge(b));
+
+        if end - start <= MAX_LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf { aabb, start, count: end - start });
+            return self.nodes.len() - 1;
+        }
+
+        // split at the median centroid along the longest axis of the box
+        let extent = aabb.max.translated_by(aabb.min.get_translating_point());
+        let axis = |p: Point3D| if extent.x >= extent.y && extent.x >= extent.z {
+            p.x
+        } else if extent.y >= extent.z {
+            p.y
+        } else {
+            p.z
+        };
+
+        self.indices[start..end].sort_by(|&a, &b| {
+            let a = axis(triangles[a].tri.aabb().center());
+            let b = axis(triangles[b].tri.aabb().center());
+            a.total_cmp(&b)
+        });
+
+        // reserve this node's slot before building the children
+        let mid = (start + end) / 2;
+        self.nodes.push(BvhNode::Leaf { aabb, start, count: 0 });
+        let index = self.nodes.len() - 1;
+
+        let left = self.build_node(triangles, start, mid);
+        let right = self.build_node(triangles, mid, end);
+        self.nodes[index] = BvhNode::Branch { aabb, left, right };
+
+        index
+    }
+
+    // returns the indices of every triangle whose subtree overlaps the frustum
+    pub fn visible_triangles(&self, frustum: &Frustum) -> Vec<usize> {
+        let mut visible = Vec::new();
+        let mut stack = Vec::new();
+
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !frustum.intersects_aabb(node.aabb()) {
+                continue;
+            }
+
+            match *node {
+                BvhNode::Leaf { start, count, .. } => visible.extend_from_slice(&self.indices[start..start + count]),
+                BvhNode::Branch { left, right, .. } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a small triangle facing -z, centered on `center`
+    fn small_triangle(center: Point3D) -> ColorTriangle {
+        let normal = Point3D::new(0.0, 0.0, -1.0);
+
+        ColorTriangle::new(
+            0xFFFFFF,
+            Triangle3D::new(
+                center.translated_by(Point3D::new(-0.1, 0.1, 0.0)),
+                center.translated_by(Point3D::new(0.1, 0.1, 0.0)),
+                center.translated_by(Point3D::new(-0.1, -0.1, 0.0)),
+            ),
+            Triangle3D::new(normal, normal, normal),
+        )
+    }
+
+    #[test]
+    fn culled_subtrees_are_skipped() {
+        // 8 triangles in front of the camera, then 8 far off to its side
+        let triangles = (0..16)
+            .map(|i| {
+                let offset = if i < 8 { 0.0 } else { 100.0 };
+                small_triangle(Point3D::new(offset + 0.2 * (i % 8) as f64 - 0.7, 0.0, 5.0))
+            })
+            .collect::<Vec<ColorTriangle>>();
+
+        let mut object = Object3D::new(triangles);
+        object.build_bvh();
+
+        let camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0));
+        let mut visible = object.bvh.as_ref().unwrap().visible_triangles(&camera.frustum().to_object_space(&object));
+        visible.sort();
+
+        assert_eq!(visible, (0..8).collect::<Vec<usize>>());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::triangles::Point3D;
+use crate::world::World;
+
+// interactive control of the objects' spin, kept apart from any windowing
+// code. pausing remembers the speed, so resuming picks up where it left off
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationControls {
+    // radians per second while running
+    pub speed: f64,
+    pub paused: bool,
+}
+
+impl AnimationControls {
+    pub fn new(speed: f64) -> Self {
+        Self { speed, paused: false }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn reverse(&mut self) {
+        self.speed = -self.speed;
+    }
+
+    // what Object3D::rotation_speed should currently be
+    pub fn rotation_speed(&self) -> f64 {
+        if self.paused { 0.0 } else { self.speed }
+    }
+
+    // gives every object in the world the current rotation speed
+    pub fn apply(&self, world: &mut World) {
+        for object in &mut world.objects {
+            object.rotation_speed = self.rotation_speed();
+        }
+    }
+
+    // turns every object back to its starting angle, without changing
+    // whether it's paused or which way it spins
+    pub fn reset(&self, world: &mut World) {
+        for object in &mut world.objects {
+            object.rotation = 0.0;
+        }
+    }
+}
+
+impl Default for AnimationControls {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+// directions the light can be nudged in, as seen from the default camera
+// looking down +z
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightMove {
+    Left,
+    Right,
+    Up,
+    Down,
+    // away from the camera
+    Forward,
+    // towards the camera
+    Back,
+}
+
+impl LightMove {
+    // the light position after moving `step` units this way
+    pub fn moved(&self, position: Point3D, step: f64) -> Point3D {
+        let offset = match self {
+            LightMove::Left => Point3D::new(-step, 0.0, 0.0),
+            LightMove::Right => Point3D::new(step, 0.0, 0.0),
+            LightMove::Up => Point3D::new(0.0, step, 0.0),
+            LightMove::Down => Point3D::new(0.0, -step, 0.0),
+            LightMove::Forward => Point3D::new(0.0, 0.0, step),
+            LightMove::Back => Point3D::new(0.0, 0.0, -step),
+        };
+
+        position.translated_by(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::cube;
+    use crate::triangles::{Camera, Light};
+    use std::time::Duration;
+
+    fn spinning_world() -> World {
+        let camera = Camera::new(Point3D::new(0.0, 0.0, -3.0), Point3D::new(0.0, 0.0, 1.0));
+        let light = Light::new(Point3D::new(2.0, 0.75, -2.0), (1.0, 1.0, 1.0));
+
+        World::new(camera, light, vec![cube(1.0)])
+    }
+
+    #[test]
+    fn pause_and_resume_keep_the_angle() {
+        let mut world = spinning_world();
+        let mut controls = AnimationControls::default();
+        world.update(Duration::from_secs(1));
+
+        controls.toggle_pause();
+        controls.apply(&mut world);
+        world.update(Duration::from_secs(3));
+        assert!((world.objects[0].rotation - 1.0).abs() < 1e-12);
+
+        controls.toggle_pause();
+        controls.apply(&mut world);
+        world.update(Duration::from_millis(3500));
+        assert!((world.objects[0].rotation - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn light_moves_one_step() {
+        let start = Point3D::new(1.0, 2.0, 3.0);
+
+        assert_eq!(LightMove::Left.moved(start, 0.25), Point3D::new(0.75, 2.0, 3.0));
+        assert_eq!(LightMove::Up.moved(start, 0.25), Point3D::new(1.0, 2.25, 3.0));
+        assert_eq!(LightMove::Back.moved(start, 0.25), Point3D::new(1.0, 2.0, 2.75));
+        assert_eq!(LightMove::Forward.moved(LightMove::Back.moved(start, 0.5), 0.5), start);
+    }
+}
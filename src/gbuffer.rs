@@ -0,0 +1,94 @@
+use std::ops::Range;
+use crate::triangles::Point3D;
+
+// what's visible at each pixel rather than its shaded color, for shading in
+// a later pass or picking without casting rays. filled by
+// World::render_gbuffer
+pub struct GBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub z_buffer: Vec<f64>,
+    // (object index, triangle index within the object) of the triangle seen
+    // at each pixel, None where nothing was drawn
+    pub ids: Vec<Option<(usize, usize)>>,
+    // interpolated world space normal and position of each pixel's surface
+    pub normals: Vec<Point3D>,
+    pub positions: Vec<Point3D>,
+}
+
+impl GBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let buffer_size = (width * height) as usize;
+        let zero = Point3D::new(0.0, 0.0, 0.0);
+
+        Self {
+            width,
+            height,
+            z_buffer: vec![f64::MAX; buffer_size],
+            ids: vec![None; buffer_size],
+            normals: vec![zero; buffer_size],
+            positions: vec![zero; buffer_size],
+        }
+    }
+
+    // resets a rect of pixels to nothing drawn
+    pub fn clear_pixels(&mut self, range_x: Range<u32>, range_y: Range<u32>) {
+        let zero = Point3D::new(0.0, 0.0, 0.0);
+
+        for y in range_y {
+            let row = (range_x.start + y * self.width) as usize..(range_x.end + y * self.width) as usize;
+
+            self.z_buffer[row.clone()].fill(f64::MAX);
+            self.ids[row.clone()].fill(None);
+            self.normals[row.clone()].fill(zero);
+            self.positions[row].fill(zero);
+        }
+    }
+
+    // the (object index, triangle index) at a pixel, if anything is there
+    pub fn id_at(&self, x: u32, y: u32) -> Option<(usize, usize)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.ids[(x + y * self.width) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::cube;
+    use crate::triangles::*;
+    use crate::world::World;
+
+    #[test]
+    fn ids_are_the_triangles_seen() {
+        let camera = Camera::new(Point3D::new(0.0, 0.0, -3.0), Point3D::new(0.0, 0.0, 1.0));
+        let light = Light::new(Point3D::new(2.0, 0.75, -2.0), (1.0, 1.0, 1.0));
+
+        // a small cube off to the left (objects are moved by minus their
+        // position) and a big one in the middle
+        let mut small = cube(0.5);
+        small.position = Point3D::new(1.0, 0.0, 0.0);
+        let world = World::new(camera, light, vec![small, cube(1.0)]);
+
+        let mut gbuffer = GBuffer::new(64, 64);
+        world.render_gbuffer(&mut gbuffer, RenderSettings::default());
+
+        assert_eq!(gbuffer.id_at(0, 0), None);
+        assert_eq!(gbuffer.id_at(8, 32).map(|(object, _)| object), Some(0));
+
+        // the big cube's front (-z) face is its last two triangles, split
+        // along the diagonal from its bottom left to top right corner
+        let upper_left = gbuffer.id_at(24, 24);
+        let lower_right = gbuffer.id_at(40, 40);
+        assert!(matches!(upper_left, Some((1, 10 | 11))));
+        assert!(matches!(lower_right, Some((1, 10 | 11))));
+        assert_ne!(upper_left, lower_right);
+
+        // and they're the ones a ray through the pixel hits first
+        assert_eq!(upper_left, world.pick(24.5 / 64.0, 24.5 / 64.0));
+        assert_eq!(lower_right, world.pick(40.5 / 64.0, 40.5 / 64.0));
+    }
+}
@@ -0,0 +1,14 @@
+pub mod bvh;
+pub mod controls;
+pub mod gbuffer;
+pub mod loader;
+pub mod matrix;
+pub mod mesh;
+pub mod primitives;
+pub mod quat;
+pub mod renderer;
+pub mod scene;
+pub mod shadow;
+pub mod texture;
+pub mod triangles;
+pub mod world;
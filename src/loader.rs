@@ -0,0 +1,348 @@
+use std::{fmt, fs::File, io::BufReader, path::Path};
+use crate::texture::Texture;
+use crate::triangles::*;
+
+#[derive(Debug)]
+pub enum GltfLoadError {
+    Gltf(gltf::Error),
+    // only triangle lists are supported for now
+    UnsupportedMode(gltf::mesh::Mode),
+    MissingPositions,
+    // a triangle refers to a vertex the primitive doesn't have
+    IndexOutOfRange { index: usize, vertices: usize },
+}
+
+impl fmt::Display for GltfLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfLoadError::Gltf(e) => write!(f, "failed to read glTF file: {}", e),
+            GltfLoadError::UnsupportedMode(mode) => write!(f, "unsupported primitive mode {:?}, only triangles are supported", mode),
+            GltfLoadError::MissingPositions => write!(f, "primitive has no vertex positions"),
+            GltfLoadError::IndexOutOfRange { index, vertices } => write!(f, "vertex index {} is out of range for {} vertices", index, vertices),
+        }
+    }
+}
+
+impl std::error::Error for GltfLoadError {}
+
+impl From<gltf::Error> for GltfLoadError {
+    fn from(e: gltf::Error) -> Self {
+        GltfLoadError::Gltf(e)
+    }
+}
+
+// loads a Wavefront OBJ file into a single white object. vertices without
+// normals (or with zero length ones) get smooth ones computed from the
+// faces, see Object3D::fill_missing_normals. texture coordinates are kept
+// when every vertex of a face has them. polygons with more than three
+// vertices are split into a fan of triangles
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<Object3D, obj::ObjError> {
+    let input = BufReader::new(File::open(path)?);
+    let raw = obj::raw::parse_obj(input)?;
+
+    // the faces index straight into these, so each vertex attribute is only
+    // converted once and nothing is deduplicated
+    let positions = raw.positions
+        .iter()
+        .map(|&(x, y, z, _)| Point3D::from([x, y, z]))
+        .collect::<Vec<Point3D>>();
+    let normals = raw.normals
+        .iter()
+        .map(|&(x, y, z)| Point3D::from([x, y, z]))
+        .collect::<Vec<Point3D>>();
+    let uvs = raw.tex_coords
+        .iter()
+        .map(|&(u, v, w)| obj_uv([u, v, w]))
+        .collect::<Vec<Point2D>>();
+
+    let zero = Point3D::new(0.0, 0.0, 0.0);
+
+    // (position, texture coordinate, normal) indices of each polygon vertex
+    let mut face = Vec::new();
+    let mut triangles = Vec::with_capacity(raw.polygons.len());
+
+    for polygon in &raw.polygons {
+        face.clear();
+
+        match polygon {
+            obj::raw::object::Polygon::P(v) => face.extend(v.iter().map(|&p| (p, None, None))),
+            obj::raw::object::Polygon::PT(v) => face.extend(v.iter().map(|&(p, t)| (p, Some(t), None))),
+            obj::raw::object::Polygon::PN(v) => face.extend(v.iter().map(|&(p, n)| (p, None, Some(n)))),
+            obj::raw::object::Polygon::PTN(v) => face.extend(v.iter().map(|&(p, t, n)| (p, Some(t), Some(n)))),
+        }
+
+        for i in 1..face.len().saturating_sub(1) {
+            let (a, b, c) = (face[0], face[i], face[i + 1]);
+
+            let normal = |n: Option<usize>| n.map_or(zero, |n| normals[n]);
+            let mut tri = ColorTriangle::new(
+                0xFFFFFF,
+                Triangle3D::new(positions[a.0], positions[b.0], positions[c.0]),
+                Triangle3D::new(normal(a.2), normal(b.2), normal(c.2)),
+            );
+
+            if let (Some(uv_a), Some(uv_b), Some(uv_c)) = (a.1, b.1, c.1) {
+                tri.uv_tri = Some(Triangle2D::new(uvs[uv_a], uvs[uv_b], uvs[uv_c]));
+            }
+
+            triangles.push(tri);
+        }
+    }
+
+    let mut object = Object3D::new(triangles);
+    object.fill_missing_normals();
+
+    // the faces share vertices, so rendering can transform each one once
+    object.build_mesh();
+
+    Ok(object)
+}
+
+// OBJ texture coordinates start at the bottom left, but Texture::sample's
+// start at the top left
+fn obj_uv(texture: [f32; 3]) -> Point2D {
+    Point2D::new(texture[0] as f64, 1.0 - texture[1] as f64)
+}
+
+// loads a PNG into a Texture, dropping its alpha channel
+pub fn load_texture<P: AsRef<Path>>(path: P) -> image::ImageResult<Texture> {
+    let image = image::open(path)?.to_rgb8();
+
+    let pixels = image
+        .pixels()
+        .map(|p| ((p[0] as u32) << 16) | ((p[1] as u32) << 8) | p[2] as u32)
+        .collect::<Vec<u32>>();
+
+    Ok(Texture::new(image.width(), image.height(), pixels))
+}
+
+// multiplies two column-major 4x4 matrices
+fn mul_matrix(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut out = [[0.0; 4]; 4];
+
+    for (col, b_col) in b.iter().enumerate() {
+        for row in 0..4 {
+            out[col][row] = (0..4).map(|k| a[k][row] * b_col[k]).sum();
+        }
+    }
+
+    out
+}
+
+fn transform_point(m: [[f32; 4]; 4], p: [f32; 3]) -> Point3D {
+    Point3D::new(
+        (m[0][0] * p[0] + m[1][0] * p[1] + m[2][0] * p[2] + m[3][0]) as f64,
+        (m[0][1] * p[0] + m[1][1] * p[1] + m[2][1] * p[2] + m[3][1]) as f64,
+        (m[0][2] * p[0] + m[1][2] * p[1] + m[2][2] * p[2] + m[3][2]) as f64,
+    )
+}
+
+// normals only get the rotation/scale part of the transform, which is fine
+// as long as the node isn't scaled non-uniformly
+fn transform_normal(m: [[f32; 4]; 4], n: [f32; 3]) -> Point3D {
+    let normal = Point3D::new(
+        (m[0][0] * n[0] + m[1][0] * n[1] + m[2][0] * n[2]) as f64,
+        (m[0][1] * n[0] + m[1][1] * n[1] + m[2][1] * n[2]) as f64,
+        (m[0][2] * n[0] + m[1][2] * n[1] + m[2][2] * n[2]) as f64,
+    );
+
+    if normal.magnitude() > 0.0 { normal.normalized() } else { normal }
+}
+
+// walks the node hierarchy, baking each node's transform into its mesh
+fn load_node(node: gltf::Node, parent: [[f32; 4]; 4], buffers: &[gltf::buffer::Data], triangles: &mut Vec<ColorTriangle>) -> Result<(), GltfLoadError> {
+    let transform = mul_matrix(parent, node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                return Err(GltfLoadError::UnsupportedMode(primitive.mode()));
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let [r, g, b, _] = primitive.material().pbr_metallic_roughness().base_color_factor();
+            let color = pack_color((r as f64, g as f64, b as f64));
+
+            let positions = reader
+                .read_positions()
+                .ok_or(GltfLoadError::MissingPositions)?
+                .map(|p| transform_point(transform, p))
+                .collect::<Vec<Point3D>>();
+
+            // missing normals are left zeroed and filled in below
+            let normals = match reader.read_normals() {
+                Some(normals) => normals.map(|n| transform_normal(transform, n)).collect::<Vec<Point3D>>(),
+                None => vec![Point3D::new(0.0, 0.0, 0.0); positions.len()],
+            };
+
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().map(|i| i as usize).collect::<Vec<usize>>(),
+                None => (0..positions.len()).collect::<Vec<usize>>(),
+            };
+
+            // the indices come from the file, so they can't be trusted
+            let vertices = usize::min(positions.len(), normals.len());
+            if let Some(&index) = indices.iter().find(|&&i| i >= vertices) {
+                return Err(GltfLoadError::IndexOutOfRange { index, vertices });
+            }
+
+            let mut primitive_object = Object3D::new(indices
+                .chunks_exact(3)
+                .map(|i| ColorTriangle::new(
+                    color,
+                    Triangle3D::new(positions[i[0]], positions[i[1]], positions[i[2]]),
+                    Triangle3D::new(normals[i[0]], normals[i[1]], normals[i[2]]),
+                ))
+                .collect());
+
+            primitive_object.fill_missing_normals();
+
+            triangles.extend(primitive_object.triangles);
+        }
+    }
+
+    for child in node.children() {
+        load_node(child, transform, buffers, triangles)?;
+    }
+
+    Ok(())
+}
+
+// loads every triangle mesh in a .gltf/.glb file into a single object
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> Result<Object3D, GltfLoadError> {
+    let (document, buffers, _) = gltf::import(path)?;
+
+    let identity = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    let mut triangles = Vec::new();
+
+    let scene = document.default_scene().or_else(|| document.scenes().next());
+    if let Some(scene) = scene {
+        for node in scene.nodes() {
+            load_node(node, identity, &buffers, &mut triangles)?;
+        }
+    }
+
+    // indexed like load_obj's, so rendering transforms each vertex once
+    let mut object = Object3D::new(triangles);
+    object.build_mesh();
+
+    Ok(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn load_gltf_cube() {
+        let object = load_gltf(fixture("cube.glb")).unwrap();
+
+        assert_eq!(object.triangles.len(), 12);
+        assert!(object.triangles.iter().all(|tri| tri.color == 0xFF7F00));
+        assert!(object.mesh.as_ref().is_some_and(|mesh| mesh.indices.len() == 12));
+    }
+
+    // normals read from a file are only as precise as f32
+    fn assert_unit_normals(object: &Object3D) {
+        for tri in &object.triangles {
+            for normal in [tri.normal_tri.a, tri.normal_tri.b, tri.normal_tri.c] {
+                assert!((normal.magnitude() - 1.0).abs() < 1e-6, "normal {:?} isn't unit length", normal);
+            }
+        }
+    }
+
+    #[test]
+    fn obj_without_normals_gets_unit_normals() {
+        let object = load_obj(fixture("cube_no_normals.obj")).unwrap();
+
+        assert_eq!(object.triangles.len(), 12);
+        assert_unit_normals(&object);
+    }
+
+    #[test]
+    fn gltf_without_normals_gets_unit_normals() {
+        assert_unit_normals(&load_gltf(fixture("cube.glb")).unwrap());
+    }
+
+    #[test]
+    fn obj_fills_only_the_missing_normals() {
+        let object = load_obj(fixture("mixed_normals.obj")).unwrap();
+
+        assert_eq!(object.triangles.len(), 3);
+        assert_unit_normals(&object);
+
+        // the face that came with a normal keeps it, the zero length one and
+        // the one without any get the plane's normal
+        let given = Point3D::new(0.0, 0.6, 0.8);
+        let computed = Point3D::new(0.0, 0.0, 1.0);
+        for (i, expected) in [given, computed, computed].into_iter().enumerate() {
+            let tri = &object.triangles[i];
+            for normal in [tri.normal_tri.a, tri.normal_tri.b, tri.normal_tri.c] {
+                assert!(normal.approx_eq(expected, 1e-6), "triangle {} has normal {:?}", i, normal);
+            }
+        }
+    }
+
+    #[test]
+    fn obj_texture_coordinates() {
+        let object = load_obj(fixture("textured_quad.obj")).unwrap();
+        assert_eq!(object.triangles.len(), 2);
+
+        // OBJ's v runs up the texture, Texture::sample's runs down
+        let uv = |u: f64, v: f64| Point2D::new(u, 1.0 - v);
+        let expected = [
+            [uv(0.0, 0.0), uv(1.0, 0.0), uv(1.0, 1.0)],
+            [uv(0.0, 0.0), uv(1.0, 1.0), uv(0.0, 1.0)],
+        ];
+
+        for (tri, expected) in object.triangles.iter().zip(expected) {
+            let uv_tri = tri.uv_tri.expect("triangle has no texture coordinates");
+            assert_eq!([uv_tri.a, uv_tri.b, uv_tri.c], expected);
+        }
+    }
+
+    #[test]
+    fn obj_matches_obj_crate_loader() {
+        let path = format!("{}/res/dragon_lowpoly.obj", env!("CARGO_MANIFEST_DIR"));
+        let object = load_obj(&path).unwrap();
+
+        // what load_obj used to do, deduplicating vertices through obj::Obj
+        let raw = obj::raw::parse_obj(BufReader::new(File::open(&path).unwrap())).unwrap();
+        let model: obj::Obj<obj::Position> = obj::Obj::new(raw).unwrap();
+
+        assert_eq!(object.triangles.len(), model.indices.len() / 3);
+        for (tri, indices) in object.triangles.iter().zip(model.indices.chunks(3)) {
+            let [a, b, c] = [0, 1, 2].map(|i| Point3D::from(model.vertices[indices[i] as usize].position));
+            assert_eq!([tri.tri.a, tri.tri.b, tri.tri.c], [a, b, c]);
+        }
+
+        let key = |p: Point3D| [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+        let vertices = object.triangles
+            .iter()
+            .flat_map(|tri| [tri.tri.a, tri.tri.b, tri.tri.c])
+            .map(key)
+            .collect::<std::collections::HashSet<[u64; 3]>>();
+        // obj::Obj keeps vertices no face uses, the triangles can't
+        let used = model.indices.iter().collect::<std::collections::HashSet<&u16>>();
+        assert_eq!(vertices.len(), used.len());
+    }
+
+    #[test]
+    fn gltf_index_out_of_range_is_an_error() {
+        // cube.glb with its first index changed from 4 to 9
+        assert!(matches!(
+            load_gltf(fixture("bad_index.glb")),
+            Err(GltfLoadError::IndexOutOfRange { index: 9, vertices: 8 }),
+        ));
+    }
+}
@@ -1,45 +1,64 @@
-use std::fs::File;
-use std::io::BufReader;
 use std::num::NonZeroU32;
-use std::rc::Rc;
-use obj::{load_obj, Obj};
-use winit::event::{Event, WindowEvent};
+use winit::event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::Window;
-use std::time::{Instant, Duration};
-use rand::Rng;
+use winit::keyboard::{KeyCode, PhysicalKey};
+use std::time::Instant;
+use rust_graphics::controls::{AnimationControls, LightMove};
+use rust_graphics::loader::load_obj;
+use rust_graphics::renderer::{draw_frame, screenshot_filename, FrameOutcome, RenderError, Renderer, Timeline};
+use rust_graphics::triangles::*;
+use rust_graphics::world::World;
 
 mod winit_app;
-mod triangles;
-use triangles::*;
+
+// how far the light moves per key press, in world units
+const LIGHT_STEP: f64 = 0.25;
+
+// how the clicked on object gets outlined
+const SELECTION_COLOR: u32 = 0xFFCC00;
+const SELECTION_THICKNESS: u32 = 2;
 
 fn main() {
     let start = Instant::now();
 
-    let obj_input = BufReader::new(File::open("res/dragon_lowpoly.obj").unwrap());
-    let model: Obj = load_obj(obj_input).unwrap();
-
-    let vertices = model.vertices
-        .into_iter()
-        .map(|v| (Point3D::new(v.position[0] as f64, v.position[1] as f64, v.position[2] as f64), Point3D::new(v.normal[0] as f64, v.normal[1] as f64, v.normal[2] as f64)))
-        .collect::<Vec::<(Point3D, Point3D)>>();
+    let object = load_obj("res/dragon_lowpoly.obj").unwrap();
+
+    let mut camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0));
+    camera.frame_object(&object, camera.fov_y_radians, camera.aspect);
+    let light = Light::new(Point3D::new(2.0, 0.75, -0.5), (1.0, 0.3, 0.0));
+    let mut world = World::new(camera, light, vec![object]);
+    let mut settings = RenderSettings::default();
+
+    // `--turntable [frames]` renders one rotation as a png sequence instead
+    // of opening a window
+    let args = std::env::args().collect::<Vec<String>>();
+    if args.get(1).map(String::as_str) == Some("--turntable") {
+        let frames = match args.get(2).map(|f| f.parse::<NonZeroU32>()) {
+            None => NonZeroU32::new(120).unwrap(),
+            Some(Ok(frames)) => frames,
+            Some(Err(_)) => {
+                eprintln!("--turntable takes a number of frames above 0");
+                std::process::exit(1);
+            }
+        };
 
-    let triangles = model.indices
-        .chunks(3)
-        .map(|indices| (indices[0] as usize, indices[1] as usize, indices[2] as usize))
-        .map(|(a, b, c)| (vertices[a], vertices[b], vertices[c]))
-        .map(|(a, b, c)| (Triangle3D::new(a.0, b.0, c.0), Triangle3D::new(a.1, b.1, c.1)))
-        .map(|(tri, normal_tri)| ColorTriangle::new(0xFFFFFF, tri, normal_tri))
-        .collect::<Vec<ColorTriangle>>();
+        Timeline::new(frames)
+            .render_turntable(&mut world, settings, 800, 600, "turntable")
+            .unwrap();
 
-    let mut object = Object3D::new(triangles);
+        return;
+    }
 
-    let mut camera = Camera::new(Point3D::new(0.0, 1.5, -5.0), Point3D::new(0.0, 0.0, -1.0));
-    let mut light = Light::new(Point3D::new(2.0, 0.75, -0.5), (1.0, 0.3, 0.0));
+    let mut cursor_position = None;
+    // the last object clicked on, which gets outlined
+    let mut selected = None;
+    let mut take_screenshot = false;
+    let mut controls = AnimationControls::default();
+    let mut screenshot_counter = 0;
 
     let event_loop = EventLoop::new().unwrap();
 
-    let mut app = winit_app::WinitAppBuilder::with_init(
+    let app = winit_app::WinitAppBuilder::with_init(
         |event_loop| {
             let window = winit_app::make_window(event_loop, |w| w);
             let context = softbuffer::Context::new(window.clone()).unwrap();
@@ -59,9 +78,9 @@ fn main() {
                 };
                 
                 if let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
-                    surface
-                        .resize(width, height)
-                        .unwrap();
+                    if let Err(e) = surface.resize(width, height) {
+                        eprintln!("{}", RenderError::Surface(e.to_string()));
+                    }
                 }
             }
             
@@ -77,29 +96,126 @@ fn main() {
                     let width = u32::from(width);
                     let height = u32::from(height);
 
-                    let mut buffer = surface.buffer_mut().unwrap();
-                    if buffer.len() as u32 != width * height {
-                        return;
+                    // a surface that can't hand out a buffer just skips this
+                    // frame, the next redraw tries again
+                    let outcome = draw_frame(surface.buffer_mut(), |mut buffer| {
+                        world.update(start.elapsed());
+
+                        // the renderer fits the aspect to the window by
+                        // itself, this keeps picking in sync with it
+                        world.camera.aspect = width as f64 / height as f64;
+
+                        // the frame is painted straight into the window's
+                        // buffer, which is skipped while it doesn't match the
+                        // window size
+                        let mut paint_buffer = Renderer::new(width, height).render_into(&world, settings, &mut buffer)?;
+
+                        if let Some(object) = selected {
+                            world.render_outline(&mut paint_buffer, settings, object, SELECTION_COLOR, SELECTION_THICKNESS);
+                        }
+
+                        if take_screenshot {
+                            take_screenshot = false;
+
+                            let filename = screenshot_filename(screenshot_counter);
+                            screenshot_counter += 1;
+
+                            match paint_buffer.save_png(&filename) {
+                                Ok(()) => println!("saved {}", filename),
+                                Err(e) => eprintln!("failed to save {}: {}", filename, e),
+                            }
+                        }
+
+                        drop(paint_buffer);
+                        buffer.present().map_err(|e| RenderError::Surface(e.to_string()))
+                    });
+
+                    match outcome {
+                        FrameOutcome::Presented | FrameOutcome::Skipped(RenderError::SizeMismatch { .. }) => {}
+                        FrameOutcome::Skipped(e) => eprintln!("skipping frame: {}", e),
                     }
-                    
-                    let time = (start.elapsed().as_millis() as f64) / 1000.0;
-                    object.rotation = time;
+                }
+            }
 
-                    let scene = Scene::new(camera, light);
+            Event::WindowEvent { window_id, event: WindowEvent::CursorMoved { position, .. } } if window_id == window.id() => {
+                cursor_position = Some(position);
+            }
 
-                    let mut paint_buffer = PaintBuffer::new(width, height);
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. },
+            } if window_id == window.id() => {
+                let size = window.inner_size();
+                let Some(position) = cursor_position else {
+                    return;
+                };
 
-                    for i in 0..paint_buffer.pixel_buffer.len() {
-                        paint_buffer.pixel_buffer[i] = 0x111111; //background color
+                if size.width > 0 && size.height > 0 {
+                    let x = position.x / size.width as f64;
+                    let y = position.y / size.height as f64;
+
+                    let picked = world.pick(x, y);
+                    match picked {
+                        Some((object, triangle)) => println!("picked triangle {} of object {}", triangle, object),
+                        None => println!("picked nothing"),
                     }
 
-                    object.paint_to_buffer(&mut paint_buffer, scene);
-                    
-                    if buffer.len() == paint_buffer.pixel_buffer.len() {
-                        buffer.copy_from_slice(&paint_buffer.pixel_buffer);
-                        buffer.present().unwrap();
+                    selected = picked.map(|(object, _)| object);
+                }
+            }
+
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::KeyboardInput {
+                    event: KeyEvent { physical_key: PhysicalKey::Code(KeyCode::F12), state: ElementState::Pressed, repeat: false, .. },
+                    ..
+                },
+            } if window_id == window.id() => {
+                take_screenshot = true;
+            }
+
+            // space pauses, R reverses the spin and 0 turns the model back
+            // to where it started. the arrow keys (or IJKL) move the light
+            // around, with U and O moving it away from and towards the camera.
+            // G shows or hides the world axes
+            Event::WindowEvent {
+                window_id,
+                event: WindowEvent::KeyboardInput {
+                    event: KeyEvent { physical_key: PhysicalKey::Code(code), state: ElementState::Pressed, repeat, .. },
+                    ..
+                },
+            } if window_id == window.id() => {
+                let light_move = match code {
+                    KeyCode::ArrowLeft | KeyCode::KeyJ => Some(LightMove::Left),
+                    KeyCode::ArrowRight | KeyCode::KeyL => Some(LightMove::Right),
+                    KeyCode::ArrowUp | KeyCode::KeyI => Some(LightMove::Up),
+                    KeyCode::ArrowDown | KeyCode::KeyK => Some(LightMove::Down),
+                    KeyCode::KeyU => Some(LightMove::Forward),
+                    KeyCode::KeyO => Some(LightMove::Back),
+                    _ => None,
+                };
+
+                if let Some(light_move) = light_move {
+                    world.light.position = light_move.moved(world.light.position, LIGHT_STEP);
+                    return;
+                }
+
+                if repeat {
+                    return;
+                }
+
+                match code {
+                    KeyCode::Space => controls.toggle_pause(),
+                    KeyCode::KeyR => controls.reverse(),
+                    KeyCode::Digit0 => controls.reset(&mut world),
+                    KeyCode::KeyG => {
+                        settings.show_axes = !settings.show_axes;
+                        return;
                     }
+                    _ => return,
                 }
+
+                controls.apply(&mut world);
             }
 
             Event::AboutToWait => {
@@ -116,5 +232,5 @@ fn main() {
         }
     });
 
-    event_loop.run_app(&mut app).unwrap();
+    winit_app::run_app(event_loop, app);
 }
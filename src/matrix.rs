@@ -0,0 +1,62 @@
+use crate::triangles::Point3D;
+
+// row-major 4x4 matrix acting on homogeneous (x, y, z, 1) column vectors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Matrix4x4 {
+    pub rows: [[f64; 4]; 4],
+}
+
+impl Matrix4x4 {
+    pub fn new(rows: [[f64; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub fn identity() -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(offset: Point3D) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Self {
+        Self::new([
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // self * other, i.e. the transform that applies `other` first and then
+    // this one
+    pub fn multiplied_by(&self, other: &Matrix4x4) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+
+        for (row, out_row) in rows.iter_mut().enumerate() {
+            for (col, out) in out_row.iter_mut().enumerate() {
+                *out = (0..4).map(|k| self.rows[row][k] * other.rows[k][col]).sum();
+            }
+        }
+
+        Self::new(rows)
+    }
+
+    // transforms a point, including the divide by w, so perspective
+    // projections work too
+    pub fn transform_point(&self, p: Point3D) -> Point3D {
+        let [x, y, z, w] = self.rows.map(|row| row[0] * p.x + row[1] * p.y + row[2] * p.z + row[3]);
+
+        Point3D::new(x / w, y / w, z / w)
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use crate::triangles::*;
+
+// triangles that share vertices by index instead of each holding their own
+// copies, so a vertex used by several triangles only has to be transformed
+// once per frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexedMesh {
+    // (position, normal) of each unique vertex
+    pub vertices: Vec<(Point3D, Point3D)>,
+    // the vertices of each triangle, wound the same way as Triangle3D's a, b, c
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl IndexedMesh {
+    pub fn new(vertices: Vec<(Point3D, Point3D)>, indices: Vec<[usize; 3]>) -> Self {
+        Self { vertices, indices }
+    }
+
+    // indexes expanded triangles, merging vertices with exactly the same
+    // position and normal. triangle i of the mesh is triangles[i]
+    pub fn from_triangles(triangles: &[ColorTriangle]) -> Self {
+        let key = |(p, n): (Point3D, Point3D)| [p.x, p.y, p.z, n.x, n.y, n.z].map(f64::to_bits);
+
+        let mut mesh = Self::default();
+        let mut lookup: HashMap<[u64; 6], usize> = HashMap::new();
+
+        for tri in triangles {
+            let corners = [
+                (tri.tri.a, tri.normal_tri.a),
+                (tri.tri.b, tri.normal_tri.b),
+                (tri.tri.c, tri.normal_tri.c),
+            ];
+
+            mesh.indices.push(corners.map(|vertex| {
+                *lookup.entry(key(vertex)).or_insert_with(|| {
+                    mesh.vertices.push(vertex);
+                    mesh.vertices.len() - 1
+                })
+            }));
+        }
+
+        mesh
+    }
+
+    // the (position, normal) vertex triangle of one of the mesh's triangles,
+    // looked up in `vertices`, which can be the mesh's own or transformed ones
+    pub fn triangle(&self, index: usize, vertices: &[(Point3D, Point3D)]) -> (Triangle3D, Triangle3D) {
+        let [a, b, c] = self.indices[index].map(|i| vertices[i]);
+
+        (Triangle3D::new(a.0, b.0, c.0), Triangle3D::new(a.1, b.1, c.1))
+    }
+
+    // expands the mesh back into triangles, all with the same color
+    pub fn to_triangles(&self, color: u32) -> Vec<ColorTriangle> {
+        (0..self.indices.len())
+            .map(|i| {
+                let (tri, normal_tri) = self.triangle(i, &self.vertices);
+                ColorTriangle::new(color, tri, normal_tri)
+            })
+            .collect()
+    }
+}
+
+// an object's mesh vertices in world space, each one transformed the first
+// time a triangle needs it and reused after that. meant to live for a
+// single frame, since the object's rotation and position change between them
+pub struct VertexCache<'a> {
+    object: &'a Object3D,
+    mesh: &'a IndexedMesh,
+    transformed: Vec<Option<(Point3D, Point3D)>>,
+    // how many vertices have been transformed so far, at most one per vertex
+    pub transforms: usize,
+}
+
+impl<'a> VertexCache<'a> {
+    pub fn new(object: &'a Object3D, mesh: &'a IndexedMesh) -> Self {
+        Self { object, mesh, transformed: vec![None; mesh.vertices.len()], transforms: 0 }
+    }
+
+    // a vertex in world space, see Object3D::to_world_vertex
+    pub fn vertex(&mut self, index: usize) -> (Point3D, Point3D) {
+        if let Some(vertex) = self.transformed[index] {
+            return vertex;
+        }
+
+        let vertex = self.object.to_world_vertex(self.mesh.vertices[index]);
+        self.transformed[index] = Some(vertex);
+        self.transforms += 1;
+
+        vertex
+    }
+
+    // the world space (position, normal) triangles of one of the mesh's
+    // triangles, see IndexedMesh::triangle
+    pub fn triangle(&mut self, index: usize) -> (Triangle3D, Triangle3D) {
+        let [a, b, c] = self.mesh.indices[index].map(|i| self.vertex(i));
+
+        (Triangle3D::new(a.0, b.0, c.0), Triangle3D::new(a.1, b.1, c.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_obj;
+    use crate::world::World;
+
+    fn dragon() -> Object3D {
+        load_obj(format!("{}/res/dragon_lowpoly.obj", env!("CARGO_MANIFEST_DIR"))).unwrap()
+    }
+
+    fn render(object: Object3D) -> Vec<u32> {
+        let mut camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0));
+        camera.frame_object(&object, camera.fov_y_radians, camera.aspect);
+        let light = Light::new(Point3D::new(2.0, 0.75, -0.5), (1.0, 0.3, 0.0));
+        let world = World::new(camera, light, vec![object]);
+
+        let mut buffer = PaintBuffer::new(160, 120);
+        world.render(&mut buffer, RenderSettings::default());
+
+        buffer.pixel_buffer.to_vec()
+    }
+
+    #[test]
+    fn indexed_render_matches_expanded() {
+        let mut indexed = dragon();
+        indexed.rotation = 0.7;
+        assert!(indexed.mesh.as_ref().is_some_and(|mesh| mesh.vertices.len() < 3 * mesh.indices.len()));
+
+        let mut expanded = dragon();
+        expanded.rotation = indexed.rotation;
+        expanded.mesh = None;
+
+        let pixels = render(indexed);
+        assert!(pixels.iter().any(|&pixel| pixel != RenderSettings::default().background));
+        assert_eq!(pixels, render(expanded));
+    }
+
+    #[test]
+    fn shared_vertices_are_transformed_once() {
+        // 36 corners, but each face's two triangles share the two on their
+        // diagonal
+        let mut cube = crate::primitives::cube(1.0);
+        cube.build_mesh();
+        cube.rotation = 0.5;
+        let mesh = cube.mesh.as_ref().unwrap();
+        assert_eq!(mesh.vertices.len(), 24);
+
+        let mut cache = VertexCache::new(&cube, mesh);
+        for i in 0..mesh.indices.len() {
+            cache.triangle(i);
+        }
+
+        assert_eq!(cache.transforms, 24);
+
+        // asking again doesn't transform anything
+        cache.triangle(0);
+        assert_eq!(cache.transforms, 24);
+    }
+}
@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+use crate::triangles::*;
+
+const COLOR: u32 = 0xFFFFFF;
+
+// the two triangles of a square face centered on `center`, spanned by the
+// unit vectors u and v. u x v must equal the (outward) normal, which makes
+// the triangles face the same way as it
+fn quad(center: Point3D, u: Point3D, v: Point3D, half_size: f64) -> [ColorTriangle; 2] {
+    let normal = u.cross(v);
+    let normal_tri = Triangle3D::new(normal, normal, normal);
+
+    let corner = |s: f64, t: f64| Point3D::new(
+        center.x + half_size * (s * u.x + t * v.x),
+        center.y + half_size * (s * u.y + t * v.y),
+        center.z + half_size * (s * u.z + t * v.z),
+    );
+
+    let p00 = corner(-1.0, -1.0);
+    let p10 = corner(1.0, -1.0);
+    let p01 = corner(-1.0, 1.0);
+    let p11 = corner(1.0, 1.0);
+
+    [
+        ColorTriangle::new(COLOR, Triangle3D::new(p00, p11, p01), normal_tri),
+        ColorTriangle::new(COLOR, Triangle3D::new(p00, p10, p11), normal_tri),
+    ]
+}
+
+// cube centered on the origin with flat shaded faces
+pub fn cube(size: f64) -> Object3D {
+    let half_size = size / 2.0;
+    let x = Point3D::new(1.0, 0.0, 0.0);
+    let y = Point3D::new(0.0, 1.0, 0.0);
+    let z = Point3D::new(0.0, 0.0, 1.0);
+
+    // (u, v) pairs, the face normal is u x v
+    let faces = [
+        (y, z),
+        (z, y),
+        (z, x),
+        (x, z),
+        (x, y),
+        (y, x),
+    ];
+
+    let triangles = faces
+        .iter()
+        .flat_map(|&(u, v)| {
+            let normal = u.cross(v);
+            let center = Point3D::new(normal.x * half_size, normal.y * half_size, normal.z * half_size);
+
+            quad(center, u, v, half_size)
+        })
+        .collect::<Vec<ColorTriangle>>();
+
+    Object3D::new(triangles)
+}
+
+// sphere centered on the origin, split into `rings` bands of latitude and
+// `sectors` bands of longitude, with smooth normals
+pub fn uv_sphere(radius: f64, rings: u32, sectors: u32) -> Object3D {
+    // unit vector for ring i (from the top) and sector j
+    let direction = |i: u32, j: u32| {
+        let theta = PI * i as f64 / rings as f64;
+        let phi = 2.0 * PI * j as f64 / sectors as f64;
+
+        Point3D::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin())
+    };
+
+    let vertex = |i: u32, j: u32| {
+        let n = direction(i, j);
+        (Point3D::new(radius * n.x, radius * n.y, radius * n.z), n)
+    };
+
+    let triangle = |a: (Point3D, Point3D), b: (Point3D, Point3D), c: (Point3D, Point3D)| {
+        ColorTriangle::new(COLOR, Triangle3D::new(a.0, b.0, c.0), Triangle3D::new(a.1, b.1, c.1))
+    };
+
+    let mut triangles = Vec::new();
+    for i in 0..rings {
+        for j in 0..sectors {
+            let top_left = vertex(i, j);
+            let top_right = vertex(i, j + 1);
+            let bottom_left = vertex(i + 1, j);
+            let bottom_right = vertex(i + 1, j + 1);
+
+            // the triangles touching the poles would be degenerate
+            if i != 0 {
+                triangles.push(triangle(top_left, top_right, bottom_left));
+            }
+            if i != rings - 1 {
+                triangles.push(triangle(top_right, bottom_right, bottom_left));
+            }
+        }
+    }
+
+    Object3D::new(triangles)
+}
+
+// single square in the xz plane centered on the origin, facing up
+pub fn plane(size: f64) -> Object3D {
+    Object3D::ground_plane(size, 1, COLOR, COLOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitive_triangle_counts() {
+        assert_eq!(cube(1.0).triangles.len(), 12);
+        assert_eq!(plane(1.0).triangles.len(), 2);
+
+        // every band gets two triangles per sector, except the ones touching
+        // the poles
+        assert_eq!(uv_sphere(1.0, 8, 12).triangles.len(), 2 * 8 * 12 - 2 * 12);
+    }
+
+    #[test]
+    fn sphere_normals_are_unit_and_outward() {
+        for tri in uv_sphere(2.0, 8, 12).triangles {
+            for (p, n) in [(tri.tri.a, tri.normal_tri.a), (tri.tri.b, tri.normal_tri.b), (tri.tri.c, tri.normal_tri.c)] {
+                assert!((n.magnitude() - 1.0).abs() < 1e-12);
+                assert!(n.dot(p) > 0.0);
+            }
+        }
+    }
+}
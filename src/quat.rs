@@ -0,0 +1,123 @@
+use crate::triangles::Point3D;
+
+// unit quaternion rotation, w + xi + yj + zk
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quat {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    // rotation by angle (in radians) around the given axis. positive angles
+    // turn the same way as Point3D::rotated_xz does around y, i.e. x towards z
+    pub fn from_axis_angle(axis: Point3D, angle: f64) -> Self {
+        let axis = axis.normalized();
+        let (sin, cos) = (-angle / 2.0).sin_cos();
+
+        Self::new(cos, axis.x * sin, axis.y * sin, axis.z * sin)
+    }
+
+    pub fn dot(&self, q: Quat) -> f64 {
+        self.w * q.w + self.x * q.x + self.y * q.y + self.z * q.z
+    }
+
+    pub fn normalized(&self) -> Self {
+        let magnitude = self.dot(*self).sqrt();
+
+        Self::new(self.w / magnitude, self.x / magnitude, self.y / magnitude, self.z / magnitude)
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    // self * q, i.e. the rotation that applies `q` first and then this one
+    pub fn mul(&self, q: Quat) -> Self {
+        Self::new(
+            self.w * q.w - self.x * q.x - self.y * q.y - self.z * q.z,
+            self.w * q.x + self.x * q.w + self.y * q.z - self.z * q.y,
+            self.w * q.y - self.x * q.z + self.y * q.w + self.z * q.x,
+            self.w * q.z + self.x * q.y - self.y * q.x + self.z * q.w,
+        )
+    }
+
+    // q * p * q^-1, with p as a pure quaternion
+    pub fn rotate_point(&self, p: Point3D) -> Point3D {
+        let rotated = self.mul(Quat::new(0.0, p.x, p.y, p.z)).mul(self.conjugate());
+
+        Point3D::new(rotated.x, rotated.y, rotated.z)
+    }
+
+    // spherical interpolation from self (t = 0) to q (t = 1), always taking
+    // the shorter way around
+    pub fn slerp(&self, q: Quat, t: f64) -> Self {
+        let mut q = q;
+        let mut cos_theta = self.dot(q);
+
+        // q and -q are the same rotation
+        if cos_theta < 0.0 {
+            q = Quat::new(-q.w, -q.x, -q.y, -q.z);
+            cos_theta = -cos_theta;
+        }
+
+        // nearly identical rotations would divide by ~0 below, a plain lerp
+        // is indistinguishable there
+        let (a, b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+
+            (((1.0 - t) * theta).sin() / sin_theta, (t * theta).sin() / sin_theta)
+        };
+
+        Quat::new(
+            a * self.w + b * q.w,
+            a * self.x + b * q.x,
+            a * self.y + b * q.y,
+            a * self.z + b * q.z,
+        )
+        .normalized()
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn y_rotation_matches_rotated_xz() {
+        let p = Point3D::new(1.0, 2.0, -0.5);
+
+        for angle in [0.3, 1.0, -2.0, 4.5] {
+            let rotated = Quat::from_axis_angle(Point3D::new(0.0, 1.0, 0.0), angle).rotate_point(p);
+            assert!(rotated.approx_eq(p.rotated_xz(angle), 1e-12), "{:?} at {}", rotated, angle);
+        }
+    }
+
+    #[test]
+    fn slerp_endpoints() {
+        let from = Quat::from_axis_angle(Point3D::new(1.0, 0.0, 0.0), 0.4);
+        let to = Quat::from_axis_angle(Point3D::new(0.0, 1.0, 1.0), 2.0);
+        let p = Point3D::new(0.3, -1.0, 2.0);
+
+        assert!(from.slerp(to, 0.0).rotate_point(p).approx_eq(from.rotate_point(p), 1e-12));
+        assert!(from.slerp(to, 1.0).rotate_point(p).approx_eq(to.rotate_point(p), 1e-12));
+    }
+}
@@ -0,0 +1,322 @@
+use std::{f64::consts::PI, fmt, num::NonZeroU32, path::Path, time::{SystemTime, UNIX_EPOCH}};
+use crate::triangles::*;
+use crate::world::World;
+
+// renders a world into a fresh buffer, with no dependency on any windowing
+// code so it can be embedded anywhere or used headlessly
+#[derive(Clone, Copy, Debug)]
+pub struct Renderer {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Renderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn render(&self, world: &World, settings: RenderSettings) -> PaintBuffer<'static> {
+        let mut buffer = PaintBuffer::new(self.width, self.height);
+        self.render_to(world, settings, &mut buffer);
+
+        buffer
+    }
+
+    // the size frames are actually rendered at, see
+    // RenderSettings::resolution_scale
+    pub fn scaled_size(&self, resolution_scale: f64) -> (u32, u32) {
+        let scale = f64::clamp(resolution_scale, 0.0, 1.0);
+
+        (
+            f64::max((self.width as f64 * scale).round(), 1.0) as u32,
+            f64::max((self.height as f64 * scale).round(), 1.0) as u32,
+        )
+    }
+
+    // whether frames can be painted straight into the output, or have to be
+    // rendered into a smaller buffer and upscaled into it
+    pub fn present_path(&self, settings: RenderSettings) -> PresentPath {
+        if self.scaled_size(settings.resolution_scale) == (self.width, self.height) {
+            PresentPath::Direct
+        } else {
+            PresentPath::Copy
+        }
+    }
+
+    // renders straight into `pixels` (e.g. a window's buffer), skipping the
+    // copy out of a separate pixel buffer unless the resolution is scaled.
+    // fails if `pixels` isn't width * height long
+    pub fn render_into<'a>(&self, world: &World, settings: RenderSettings, pixels: &'a mut [u32]) -> Result<PaintBuffer<'a>, RenderError> {
+        let expected = (self.width * self.height) as usize;
+        let actual = pixels.len();
+
+        let mut buffer = PaintBuffer::from_pixels(self.width, self.height, pixels)
+            .ok_or(RenderError::SizeMismatch { expected, actual })?;
+        self.render_to(world, settings, &mut buffer);
+
+        Ok(buffer)
+    }
+
+    // width / height of the viewport's part of the output in pixels, which
+    // the camera's aspect is replaced with so images are never stretched
+    pub fn aspect(&self, viewport: Viewport) -> f64 {
+        let width = (viewport.rect.max.x - viewport.rect.min.x) * self.width as f64;
+        let height = (viewport.rect.max.y - viewport.rect.min.y) * self.height as f64;
+
+        width / height
+    }
+
+    fn render_to(&self, world: &World, settings: RenderSettings, buffer: &mut PaintBuffer) {
+        let camera = Camera { aspect: self.aspect(settings.viewport), ..world.camera };
+
+        match self.present_path(settings) {
+            PresentPath::Direct => world.render_viewport(buffer, camera, settings.viewport, settings),
+            PresentPath::Copy => {
+                let (width, height) = self.scaled_size(settings.resolution_scale);
+                let mut scaled = PaintBuffer::new(width, height);
+                world.render_viewport(&mut scaled, camera, settings.viewport, settings);
+
+                scaled.upscale_to(buffer);
+            }
+        }
+    }
+}
+
+// see Renderer::present_path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresentPath {
+    // rendering at full resolution, paint into the output in place
+    Direct,
+    // rendering at a reduced resolution, upscale into the output
+    Copy,
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    // the target buffer doesn't have width * height pixels, which happens
+    // for a frame or two while a window is being resized
+    SizeMismatch { expected: usize, actual: usize },
+    // the window surface couldn't be resized, acquired or presented
+    Surface(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::SizeMismatch { expected, actual } => write!(f, "target buffer has {} pixels, expected {}", actual, expected),
+            RenderError::Surface(e) => write!(f, "surface error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+// what became of a frame, see draw_frame
+#[derive(Debug)]
+pub enum FrameOutcome {
+    Presented,
+    // the surface or the renderer failed, the next redraw tries again
+    Skipped(RenderError),
+}
+
+// draws a frame into the buffer a window surface handed out for it. if the
+// surface couldn't hand one out, `draw` isn't called at all and the frame is
+// skipped rather than unwrapped, as it is if drawing fails
+pub fn draw_frame<T, E: fmt::Display>(acquired: Result<T, E>, draw: impl FnOnce(T) -> Result<(), RenderError>) -> FrameOutcome {
+    let drawn = acquired
+        .map_err(|e| RenderError::Surface(e.to_string()))
+        .and_then(draw);
+
+    match drawn {
+        Ok(()) => FrameOutcome::Presented,
+        Err(e) => FrameOutcome::Skipped(e),
+    }
+}
+
+// renders a single frame of the world without any window, at any size. the
+// camera's aspect is fitted to width / height, see Renderer::aspect
+pub fn render_headless(world: &World, settings: RenderSettings, width: u32, height: u32) -> PaintBuffer<'static> {
+    Renderer::new(width, height).render(world, settings)
+}
+
+// a png filename stamped with the current time. the counter keeps
+// screenshots taken within the same second apart
+pub fn screenshot_filename(counter: u32) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap_or(0);
+
+    format!("screenshot_{}_{}.png", timestamp, counter)
+}
+
+// frame-indexed animation, so recordings don't depend on how long each frame
+// took to render
+#[derive(Clone, Copy, Debug)]
+pub struct Timeline {
+    // a rotation can't take zero frames
+    pub frames_per_rotation: NonZeroU32,
+}
+
+impl Timeline {
+    pub fn new(frames_per_rotation: NonZeroU32) -> Self {
+        Self { frames_per_rotation }
+    }
+
+    // rotation (in radians, within [0, 2pi)) of the objects at a frame
+    pub fn rotation_at(&self, frame: u32) -> f64 {
+        let frames_per_rotation = self.frames_per_rotation.get();
+        let frame = frame % frames_per_rotation;
+
+        2.0 * PI * frame as f64 / frames_per_rotation as f64
+    }
+
+    // renders one full rotation of every object as frame_0000.png,
+    // frame_0001.png, ... in `dir`
+    pub fn render_turntable<P: AsRef<Path>>(&self, world: &mut World, settings: RenderSettings, width: u32, height: u32, dir: P) -> image::ImageResult<()> {
+        std::fs::create_dir_all(&dir)?;
+
+        for frame in 0..self.frames_per_rotation.get() {
+            let rotation = self.rotation_at(frame);
+            for object in &mut world.objects {
+                object.rotation = rotation;
+            }
+
+            let buffer = render_headless(world, settings, width, height);
+            buffer.save_png(dir.as_ref().join(format!("frame_{:04}.png", frame)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::plane;
+
+    // a 1.02 wide square seen from 2 above, which covers the middle 0.51 of
+    // the screen on each axis without any pixel centers on its edges
+    fn square_world() -> World {
+        let camera = Camera::new(Point3D::new(0.0, 2.0, 0.0), Point3D::new(0.0, -1.0, 0.0));
+        let light = Light::new(Point3D::new(0.0, 3.0, 0.0), (1.0, 1.0, 1.0));
+
+        World::new(camera, light, vec![plane(1.02)])
+    }
+
+    fn painted_pixels(buffer: &PaintBuffer, background: u32) -> usize {
+        buffer.pixel_buffer.iter().filter(|&&pixel| pixel != background).count()
+    }
+
+    #[test]
+    fn render_paints_the_covered_pixels() {
+        let settings = RenderSettings::default();
+        let buffer = Renderer::new(64, 64).render(&square_world(), settings);
+
+        assert_eq!((buffer.width, buffer.height), (64, 64));
+        // columns and rows 16 through 48
+        assert_eq!(painted_pixels(&buffer, settings.background), 33 * 33);
+    }
+
+    #[test]
+    fn screenshot_filenames_are_unique() {
+        let names = (0..100).map(screenshot_filename).collect::<std::collections::HashSet<String>>();
+
+        assert_eq!(names.len(), 100);
+        assert!(names.iter().all(|name| name.starts_with("screenshot_") && name.ends_with(".png")));
+    }
+
+    #[test]
+    fn timeline_repeats_every_rotation() {
+        let timeline = Timeline::new(NonZeroU32::new(24).unwrap());
+
+        for frame in [0, 5, 23] {
+            assert!((timeline.rotation_at(frame) - timeline.rotation_at(frame + 24)).abs() < 1e-12);
+        }
+        assert!((timeline.rotation_at(6) - PI / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn render_into_matches_render() {
+        let renderer = Renderer::new(64, 64);
+        let settings = RenderSettings::default();
+        assert_eq!(renderer.present_path(settings), PresentPath::Direct);
+        assert_eq!(renderer.present_path(RenderSettings { resolution_scale: 0.5, ..settings }), PresentPath::Copy);
+
+        for settings in [settings, RenderSettings { resolution_scale: 0.5, ..settings }] {
+            let mut pixels = vec![0; 64 * 64];
+            renderer.render_into(&square_world(), settings, &mut pixels).unwrap();
+
+            assert_eq!(pixels, renderer.render(&square_world(), settings).pixel_buffer.to_vec());
+        }
+
+        let mut wrong_size = vec![0; 64 * 63];
+        assert!(matches!(
+            renderer.render_into(&square_world(), settings, &mut wrong_size),
+            Err(RenderError::SizeMismatch { expected: 4096, actual: 4032 }),
+        ));
+    }
+
+    #[test]
+    fn failed_surface_skips_the_frame() {
+        let mut drawn = false;
+        let lost: Result<Vec<u32>, &str> = Err("surface lost");
+
+        match draw_frame(lost, |_| { drawn = true; Ok(()) }) {
+            FrameOutcome::Skipped(e @ RenderError::Surface(_)) => assert_eq!(e.to_string(), "surface error: surface lost"),
+            other => panic!("expected a skipped frame, got {:?}", other),
+        }
+        assert!(!drawn);
+
+        let acquired: Result<Vec<u32>, &str> = Ok(vec![0; 64 * 64]);
+        let outcome = draw_frame(acquired, |mut pixels| {
+            drawn = true;
+            Renderer::new(64, 64).render_into(&square_world(), RenderSettings::default(), &mut pixels).map(|_| ())
+        });
+        assert!(matches!(outcome, FrameOutcome::Presented));
+        assert!(drawn);
+
+        // a buffer of the wrong size is skipped too
+        let resized: Result<Vec<u32>, &str> = Ok(vec![0; 64 * 63]);
+        let outcome = draw_frame(resized, |mut pixels| {
+            Renderer::new(64, 64).render_into(&square_world(), RenderSettings::default(), &mut pixels).map(|_| ())
+        });
+        assert!(matches!(outcome, FrameOutcome::Skipped(RenderError::SizeMismatch { .. })));
+    }
+
+    #[test]
+    fn half_resolution_renders_a_quarter_of_the_pixels() {
+        let renderer = Renderer::new(64, 64);
+        let (width, height) = renderer.scaled_size(0.5);
+        assert_eq!(width * height, 64 * 64 / 4);
+
+        let settings = RenderSettings { resolution_scale: 0.5, ..RenderSettings::default() };
+        let buffer = renderer.render(&square_world(), settings);
+        assert_eq!((buffer.width, buffer.height), (64, 64));
+
+        // every rendered pixel covers a 2x2 block of the output
+        for y in 0..64 {
+            for x in 0..64 {
+                assert_eq!(buffer.pixel_buffer[x + y * 64], buffer.pixel_buffer[(x & !1) + (y & !1) * 64]);
+            }
+        }
+        assert!(painted_pixels(&buffer, settings.background) > 0);
+    }
+
+    #[test]
+    fn full_hd_render_is_not_stretched() {
+        let settings = RenderSettings::default();
+        let buffer = render_headless(&square_world(), settings, 1920, 1080);
+        assert_eq!(buffer.pixel_buffer.len(), 1920 * 1080);
+
+        let painted = (0..1920 * 1080)
+            .filter(|&index| buffer.pixel_buffer[index] != settings.background)
+            .map(|index| (index % 1920, index / 1920))
+            .collect::<Vec<(usize, usize)>>();
+        let width = painted.iter().map(|p| p.0).max().unwrap() - painted.iter().map(|p| p.0).min().unwrap() + 1;
+        let height = painted.iter().map(|p| p.1).max().unwrap() - painted.iter().map(|p| p.1).min().unwrap() + 1;
+
+        // the square stays square
+        assert!(width.abs_diff(height) <= 1, "{}x{}", width, height);
+    }
+}
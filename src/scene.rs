@@ -0,0 +1,197 @@
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+use serde::{Deserialize, Serialize};
+use crate::loader::{load_gltf, load_obj, GltfLoadError};
+use crate::triangles::*;
+use crate::world::World;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Obj(obj::ObjError),
+    Gltf(GltfLoadError),
+    // SceneFile::from_world was given a different number of paths than the
+    // world has objects
+    PathCount { objects: usize, paths: usize },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "failed to access scene file: {}", e),
+            SceneError::Json(e) => write!(f, "invalid scene file: {}", e),
+            SceneError::Obj(e) => write!(f, "failed to load OBJ model: {}", e),
+            SceneError::Gltf(e) => write!(f, "failed to load glTF model: {}", e),
+            SceneError::PathCount { objects, paths } => write!(f, "got {} model paths for {} objects", paths, objects),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        SceneError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(e: serde_json::Error) -> Self {
+        SceneError::Json(e)
+    }
+}
+
+// a model file and how it's placed in the scene
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneObject {
+    pub path: PathBuf,
+    pub position: Point3D,
+    pub rotation: f64,
+    pub rotation_speed: f64,
+}
+
+// everything needed to reproduce a view, stored as JSON by save_scene
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneFile {
+    pub camera: Camera,
+    pub light: Light,
+    pub light_orbit: Option<LightOrbit>,
+    pub objects: Vec<SceneObject>,
+    pub settings: RenderSettings,
+}
+
+impl SceneFile {
+    // captures a world's current view. worlds don't remember where their
+    // objects were loaded from, so `paths` gives them in the same order as
+    // world.objects, one per object
+    pub fn from_world(world: &World, paths: &[PathBuf], settings: RenderSettings) -> Result<Self, SceneError> {
+        if paths.len() != world.objects.len() {
+            return Err(SceneError::PathCount { objects: world.objects.len(), paths: paths.len() });
+        }
+
+        let objects = world.objects
+            .iter()
+            .zip(paths)
+            .map(|(object, path)| SceneObject {
+                path: path.clone(),
+                position: object.position,
+                rotation: object.rotation,
+                rotation_speed: object.rotation_speed,
+            })
+            .collect();
+
+        Ok(Self {
+            camera: world.camera,
+            light: world.light,
+            light_orbit: world.light_orbit,
+            objects,
+            settings,
+        })
+    }
+
+    // loads every object (.gltf and .glb as glTF, anything else as OBJ) and
+    // places it as it was saved
+    pub fn to_world(&self) -> Result<World, SceneError> {
+        let mut objects = Vec::new();
+
+        for scene_object in &self.objects {
+            let extension = scene_object.path.extension().and_then(|e| e.to_str());
+
+            let mut object = match extension {
+                Some("gltf") | Some("glb") => load_gltf(&scene_object.path).map_err(SceneError::Gltf)?,
+                _ => load_obj(&scene_object.path).map_err(SceneError::Obj)?,
+            };
+
+            object.position = scene_object.position;
+            object.rotation = scene_object.rotation;
+            object.rotation_speed = scene_object.rotation_speed;
+
+            objects.push(object);
+        }
+
+        let mut world = World::new(self.camera, self.light, objects);
+        world.light_orbit = self.light_orbit;
+
+        Ok(world)
+    }
+}
+
+pub fn load_scene<P: AsRef<Path>>(path: P) -> Result<SceneFile, SceneError> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn save_scene<P: AsRef<Path>>(scene: &SceneFile, path: P) -> Result<(), SceneError> {
+    fs::write(path, serde_json::to_string_pretty(scene)?)?;
+
+    Ok(())
+}
+
+// JSON has no infinity, so infinite values (like the far plane of
+// Camera::new) are written as null
+pub(crate) mod infinity_as_null {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if *value == f64::INFINITY {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_some(value)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::INFINITY))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name))
+    }
+
+    fn test_world() -> World {
+        let mut camera = Camera::new(Point3D::new(1.0, 2.0, -3.0), Point3D::new(0.0, -0.5, 1.0));
+        camera.far = 50.0;
+        let light = Light::new(Point3D::new(2.0, 0.75, -2.0), (1.0, 0.5, 0.25));
+
+        let mut world = World::new(camera, light, vec![load_obj(fixture("cube_no_normals.obj")).unwrap()]);
+        world.objects[0].position = Point3D::new(0.5, 0.0, 1.0);
+        world.objects[0].rotation = 0.75;
+        world.light_orbit = Some(LightOrbit::new(Point3D::new(0.0, 1.0, 0.0), 2.0, 4.0));
+
+        world
+    }
+
+    #[test]
+    fn from_world_needs_a_path_per_object() {
+        let world = test_world();
+
+        assert!(matches!(
+            SceneFile::from_world(&world, &[], RenderSettings::default()),
+            Err(SceneError::PathCount { objects: 1, paths: 0 }),
+        ));
+    }
+
+    #[test]
+    fn scene_round_trips_through_json() {
+        let world = test_world();
+        let settings = RenderSettings { ambient: 0.3, ssao: true, ..RenderSettings::default() };
+        let scene = SceneFile::from_world(&world, &[fixture("cube_no_normals.obj")], settings).unwrap();
+
+        let json = serde_json::to_string(&scene).unwrap();
+        let loaded: SceneFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, scene);
+
+        // including Camera::new's infinite far plane
+        let infinite = SceneFile { camera: Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0)), ..scene };
+        let loaded: SceneFile = serde_json::from_str(&serde_json::to_string(&infinite).unwrap()).unwrap();
+        assert_eq!(loaded.camera.far, f64::INFINITY);
+
+        let rebuilt = loaded.to_world().unwrap();
+        assert_eq!(rebuilt.objects[0].position, world.objects[0].position);
+        assert_eq!(rebuilt.objects[0].rotation, world.objects[0].rotation);
+    }
+}
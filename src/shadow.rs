@@ -0,0 +1,127 @@
+use crate::triangles::*;
+
+// depth of the scene as seen from a light, used to test whether a point is
+// lit or sits behind something closer to the light
+pub struct ShadowMap {
+    pub size: u32,
+    pub depth: Vec<f64>,
+    pub bias: f64,
+    right: Point3D,
+    up: Point3D,
+    forward: Point3D,
+    origin: Point3D,
+}
+
+impl ShadowMap {
+    // builds the light's basis. spotlights look down their direction, point
+    // lights look towards the world origin
+    fn new(light: Light, size: u32, bias: f64) -> Self {
+        let forward = match light.kind {
+            LightKind::Spot { direction, .. } => direction,
+            LightKind::Point => light.position.get_translating_point(),
+        };
+        let forward = if forward.magnitude() > 0.0 { forward.normalized() } else { Point3D::new(0.0, 0.0, 1.0) };
+        let (right, up) = orthonormal_basis(forward);
+
+        Self {
+            size,
+            depth: vec![f64::MAX; (size * size) as usize],
+            bias,
+            right,
+            up,
+            forward,
+            origin: light.position,
+        }
+    }
+
+    // moves a world space point into the light's space, where +z is forward.
+    // x and y are halved so the shadow map covers a 90 degree field of view
+    fn to_light_space(&self, p: Point3D) -> Point3D {
+        let d = p.translated_by(self.origin.get_translating_point());
+
+        Point3D::new(0.5 * d.dot(self.right), 0.5 * d.dot(self.up), d.dot(self.forward))
+    }
+
+    // returns whether a world space point is hidden from the light
+    pub fn is_shadowed(&self, p: Point3D) -> bool {
+        let (pixel, depth) = self.to_light_space(p).project_to_screen(light_camera(), self.size, self.size);
+        let Some((x, y)) = pixel else {
+            return false;
+        };
+
+        if x < 0 || y < 0 || x >= self.size as i32 || y >= self.size as i32 {
+            return false;
+        }
+
+        let index = (x as u32 + y as u32 * self.size) as usize;
+        depth - self.bias > self.depth[index]
+    }
+}
+
+// the light space triangles are rasterized from the origin
+fn light_camera() -> Camera {
+    Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0))
+}
+
+// renders the depth of every object from the light's point of view into a
+// size x size shadow map. front_face should match the one used for the frame
+pub fn render_shadow_map(objects: &[Object3D], light: Light, size: u32, bias: f64, front_face: FrontFace) -> ShadowMap {
+    let mut shadow_map = ShadowMap::new(light, size, bias);
+    let mut buffer = PaintBuffer::new(size, size);
+
+    let scene = Scene::new(light_camera(), light);
+    let settings = RenderSettings { front_face, ..RenderSettings::default() };
+
+    for object in objects {
+        for tri in object.world_triangles() {
+            let light_tri = Triangle3D::new(
+                shadow_map.to_light_space(tri.tri.a),
+                shadow_map.to_light_space(tri.tri.b),
+                shadow_map.to_light_space(tri.tri.c),
+            );
+
+            // skip anything behind the light, it can't cast a shadow
+            if light_tri.a.z <= 0.0 || light_tri.b.z <= 0.0 || light_tri.c.z <= 0.0 {
+                continue;
+            }
+
+            light_tri.paint_to_buffer(&mut buffer, scene, settings, |_, _, _| (0, (0.0, 0.0, 0.0)));
+        }
+    }
+
+    shadow_map.depth = buffer.z_buffer;
+    shadow_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::cube;
+    use crate::world::World;
+
+    #[test]
+    fn occluded_fragments_are_darker() {
+        // a cube floating over the ground, lit from above and to the right so
+        // its shadow falls to the left, seen from straight above
+        let mut occluder = cube(1.0);
+        // to_world subtracts the position, so this puts it 1.5 above the ground
+        occluder.position = Point3D::new(0.0, -1.5, 0.0);
+
+        let camera = Camera::new(Point3D::new(0.0, 8.0, 0.0), Point3D::new(0.0, -1.0, 0.0));
+        let light = Light::new(Point3D::new(2.0, 5.0, 0.0), (1.0, 1.0, 1.0));
+        let world = World::new(camera, light, vec![Object3D::ground_plane(6.0, 1, 0xFFFFFF, 0xFFFFFF), occluder]);
+
+        let render = |shadows: bool| {
+            let mut buffer = PaintBuffer::new(64, 64);
+            world.render(&mut buffer, RenderSettings { shadows, ..RenderSettings::default() });
+            buffer
+        };
+
+        let (lit, shadowed) = (render(false), render(true));
+        let brightness = |buffer: &PaintBuffer, x: u32, y: u32| buffer.pixel_buffer[(x + y * 64) as usize] & 0xFF;
+
+        // (-1.5, 0, 0) is in the cube's shadow, (2, 0, 0) isn't
+        assert!(brightness(&shadowed, 20, 32) < brightness(&lit, 20, 32));
+        assert_eq!(brightness(&shadowed, 48, 32), brightness(&lit, 48, 32));
+    }
+}
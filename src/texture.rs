@@ -0,0 +1,198 @@
+use crate::triangles::Point3D;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    // uses the single texel the UV coordinate falls in
+    #[default]
+    Nearest,
+    // blends the four texels surrounding the UV coordinate
+    Bilinear,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    // coordinates outside of [0, 1] stick to the edge texels
+    #[default]
+    Clamp,
+    // the texture tiles
+    Repeat,
+    // the texture tiles, flipping every other copy
+    Mirror,
+}
+
+impl WrapMode {
+    // maps a texture coordinate into [0, 1]
+    fn wrap_coord(&self, t: f64) -> f64 {
+        match self {
+            WrapMode::Clamp => t.clamp(0.0, 1.0),
+            WrapMode::Repeat => t.rem_euclid(1.0),
+            WrapMode::Mirror => {
+                let t = t.rem_euclid(2.0);
+                if t > 1.0 { 2.0 - t } else { t }
+            }
+        }
+    }
+
+    // maps a texel index into [0, size)
+    fn wrap_index(&self, i: i64, size: i64) -> i64 {
+        match self {
+            WrapMode::Clamp => i.clamp(0, size - 1),
+            WrapMode::Repeat => i.rem_euclid(size),
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                if i >= size { 2 * size - 1 - i } else { i }
+            }
+        }
+    }
+}
+
+// an image in the same 0x00RRGGBB format as the PaintBuffer
+#[derive(Clone, Debug)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+    pub filter: FilterMode,
+    pub wrap: WrapMode,
+}
+
+impl Texture {
+    pub fn new(width: u32, height: u32, pixels: Vec<u32>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize, "texture size doesn't match its pixel count");
+
+        Self {
+            width,
+            height,
+            pixels,
+            filter: FilterMode::default(),
+            wrap: WrapMode::default(),
+        }
+    }
+
+    // gets a texel, wrapping out of range coordinates with the wrap mode
+    fn texel(&self, x: i64, y: i64) -> u32 {
+        let x = self.wrap.wrap_index(x, self.width as i64) as u32;
+        let y = self.wrap.wrap_index(y, self.height as i64) as u32;
+
+        self.pixels[(x + y * self.width) as usize]
+    }
+
+    // samples the texture at a UV coordinate, where (0, 0) is the top left
+    pub fn sample(&self, u: f64, v: f64) -> u32 {
+        let x = self.wrap.wrap_coord(u) * self.width as f64;
+        let y = self.wrap.wrap_coord(v) * self.height as f64;
+
+        match self.filter {
+            FilterMode::Nearest => self.texel(x.floor() as i64, y.floor() as i64),
+            FilterMode::Bilinear => {
+                // texel centers sit at half-integer coordinates
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let x0 = x.floor();
+                let y0 = y.floor();
+                let tx = x - x0;
+                let ty = y - y0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = lerp_color(self.texel(x0, y0), self.texel(x0 + 1, y0), tx);
+                let bottom = lerp_color(self.texel(x0, y0 + 1), self.texel(x0 + 1, y0 + 1), tx);
+
+                lerp_color(top, bottom, ty)
+            }
+        }
+    }
+}
+
+// linearly interpolates each channel of two 0x00RRGGBB colors
+pub fn lerp_color(a: u32, b: u32, t: f64) -> u32 {
+    let channel = |shift: u32| {
+        let a = ((a >> shift) & 0xFF) as f64;
+        let b = ((b >> shift) & 0xFF) as f64;
+
+        ((a + (b - a) * t).round() as u32) << shift
+    };
+
+    channel(16) | channel(8) | channel(0)
+}
+
+// the faces of a cube map, in the order CubeMap stores them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+// six textures on the inside of a cube around the viewer, looked up by
+// direction, e.g. for a skybox. the faces are laid out like OpenGL's, which
+// with +y up and +z forward means each one reads the right way around when
+// looked at from the inside
+#[derive(Clone, Debug)]
+pub struct CubeMap {
+    // indexed by CubeFace
+    pub faces: [Texture; 6],
+}
+
+impl CubeMap {
+    pub fn new(faces: [Texture; 6]) -> Self {
+        Self { faces }
+    }
+
+    // the face a direction points at, along with where on that face as a
+    // (u, v) coordinate with (0, 0) at the top left
+    pub fn face_and_uv(dir: Point3D) -> (CubeFace, f64, f64) {
+        let (x, y, z) = (dir.x, dir.y, dir.z);
+        let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+        // (face, horizontal, vertical, the major axis' magnitude)
+        let (face, s, t, major) = if ax >= ay && ax >= az {
+            if x > 0.0 { (CubeFace::PositiveX, -z, -y, ax) } else { (CubeFace::NegativeX, z, -y, ax) }
+        } else if ay >= az {
+            if y > 0.0 { (CubeFace::PositiveY, x, z, ay) } else { (CubeFace::NegativeY, x, -z, ay) }
+        } else if z > 0.0 {
+            (CubeFace::PositiveZ, x, -y, az)
+        } else {
+            (CubeFace::NegativeZ, -x, -y, az)
+        };
+
+        (face, 0.5 * (s / major + 1.0), 0.5 * (t / major + 1.0))
+    }
+
+    // the color seen looking along a direction
+    pub fn sample(&self, dir: Point3D) -> u32 {
+        let (face, u, v) = CubeMap::face_and_uv(dir);
+
+        self.faces[face as usize].sample(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bilinear_center_averages_four_texels() {
+        let mut texture = Texture::new(2, 2, vec![0x000000, 0x400000, 0x004000, 0x000040]);
+        texture.filter = FilterMode::Bilinear;
+
+        assert_eq!(texture.sample(0.5, 0.5), 0x101010);
+    }
+
+    #[test]
+    fn wrap_modes_outside_unit_range() {
+        let mut texture = Texture::new(4, 1, vec![0x000000, 0x111111, 0x222222, 0x333333]);
+
+        texture.wrap = WrapMode::Repeat;
+        assert_eq!(texture.sample(1.5, 0.0), texture.sample(0.5, 0.0));
+        assert_eq!(texture.sample(1.25, 0.0), 0x111111);
+
+        // mirroring flips the copy past 1.0 around it, so 1.5 lands back on
+        // 0.5 and 1.25 on 0.75
+        texture.wrap = WrapMode::Mirror;
+        assert_eq!(texture.sample(1.5, 0.0), texture.sample(0.5, 0.0));
+        assert_eq!(texture.sample(1.25, 0.0), 0x333333);
+    }
+}
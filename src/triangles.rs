@@ -1,6 +1,13 @@
-use std::{cmp, ops::Range};
-
-#[derive(Clone, Copy, Debug)]
+use crate::bvh::Bvh;
+use crate::gbuffer::GBuffer;
+use crate::matrix::Matrix4x4;
+use crate::mesh::{IndexedMesh, VertexCache};
+use crate::shadow::ShadowMap;
+use crate::texture::{lerp_color, CubeMap, Texture};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, f64::consts::PI, fs::File, io::{self, BufWriter, Write}, ops::{Deref, DerefMut, Range}, path::Path};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Point2D {
     pub x: f64,
     pub y: f64,
@@ -14,6 +21,171 @@ impl Point2D {
     pub fn translated_by(&self, offset: Point2D) -> Self {
         Point2D::new(self.x + offset.x, self.y + offset.y)
     }
+
+    // the reverse of translated_by, i.e. self - offset
+    pub fn sub(&self, offset: Point2D) -> Self {
+        Point2D::new(self.x - offset.x, self.y - offset.y)
+    }
+
+    // the 2D cross product, i.e. the z component of the 3D cross product of
+    // the two vectors. positive when `other` is clockwise from this vector on
+    // a screen where y points down
+    pub fn perp_dot(&self, other: Point2D) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    // checks whether both components are within eps of the other point's
+    pub fn approx_eq(&self, other: Point2D, eps: f64) -> bool {
+        (self.x - other.x).abs() <= eps && (self.y - other.y).abs() <= eps
+    }
+}
+
+// finds where the segments a0-a1 and b0-b1 cross, if they do. parallel
+// segments never count as intersecting, even when they're collinear and
+// overlap, since there's no single crossing point
+pub fn segment_intersect(a0: Point2D, a1: Point2D, b0: Point2D, b1: Point2D) -> Option<Point2D> {
+    let r = Point2D::new(a1.x - a0.x, a1.y - a0.y);
+    let s = Point2D::new(b1.x - b0.x, b1.y - b0.y);
+
+    let denominator = r.perp_dot(s);
+    if denominator.abs() < 1e-12 {
+        return None;
+    }
+
+    let offset = Point2D::new(b0.x - a0.x, b0.y - a0.y);
+    let t = offset.perp_dot(s) / denominator;
+    let u = offset.perp_dot(r) / denominator;
+
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(Point2D::new(a0.x + t * r.x, a0.y + t * r.y))
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub min: Point2D,
+    pub max: Point2D,
+}
+
+impl Rect {
+    pub fn new(min: Point2D, max: Point2D) -> Self {
+        Self { min, max }
+    }
+
+    // the whole screen, in the same [0, 1] coordinates as projected triangles
+    pub fn unit() -> Self {
+        Self::new(Point2D::new(0.0, 0.0), Point2D::new(1.0, 1.0))
+    }
+
+    // maps a point from [0, 1] coordinates into this rect
+    pub fn from_unit(&self, p: Point2D) -> Point2D {
+        Point2D::new(
+            self.min.x + p.x * (self.max.x - self.min.x),
+            self.min.y + p.y * (self.max.y - self.min.y),
+        )
+    }
+
+    // the inverse of from_unit, maps a point in this rect to [0, 1]
+    pub fn to_unit(&self, p: Point2D) -> Point2D {
+        Point2D::new(
+            (p.x - self.min.x) / (self.max.x - self.min.x),
+            (p.y - self.min.y) / (self.max.y - self.min.y),
+        )
+    }
+
+    // the pixels of a width x height buffer whose sample points are inside
+    // the rect. the max edges are exclusive, so rects sharing an edge don't
+    // share pixels
+    pub fn pixel_range(&self, width: u32, height: u32) -> (Range<u32>, Range<u32>) {
+        let to_px = |v: f64, size: u32| f64::clamp((v * size as f64).ceil(), 0.0, size as f64) as u32;
+
+        (
+            to_px(self.min.x, width)..to_px(self.max.x, width),
+            to_px(self.min.y, height)..to_px(self.max.y, height),
+        )
+    }
+}
+
+// where a camera's view goes in the buffer: the rect it fills, and where the
+// middle of the view lands inside that rect. moving the center away from
+// Camera::VIEWPORT_CENTER shifts the image without changing the perspective,
+// for off-axis projections
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    pub rect: Rect,
+    // in the rect's own [0, 1] coordinates
+    pub center: Point2D,
+}
+
+impl Viewport {
+    // a rect with the view centered in it
+    pub fn new(rect: Rect) -> Self {
+        Self { rect, center: Camera::VIEWPORT_CENTER }
+    }
+
+    // the whole buffer, with the view centered
+    pub fn full() -> Self {
+        Self::new(Rect::unit())
+    }
+
+    pub fn with_center(self, center: Point2D) -> Self {
+        Self { center, ..self }
+    }
+
+    // maps a point from [0, 1] screen coordinates (see
+    // Camera::screen_transform) into the buffer
+    pub fn from_unit(&self, p: Point2D) -> Point2D {
+        self.rect.from_unit(p.translated_by(self.center.sub(Camera::VIEWPORT_CENTER)))
+    }
+
+    // the inverse of from_unit, from the buffer back to screen coordinates
+    pub fn to_unit(&self, p: Point2D) -> Point2D {
+        self.rect.to_unit(p).sub(self.center.sub(Camera::VIEWPORT_CENTER))
+    }
+}
+
+// see https://en.wikipedia.org/wiki/Sutherland%E2%80%93Hodgman_algorithm
+// clips a convex polygon against a rectangle, one edge at a time. returns an
+// empty polygon if nothing is left
+pub fn clip_to_rect(poly: &[Point2D], rect: Rect) -> Vec<Point2D> {
+    // how far inside each of the four edges a point is, negative if outside
+    let distances: [fn(Point2D, Rect) -> f64; 4] = [
+        |p, r| p.x - r.min.x,
+        |p, r| r.max.x - p.x,
+        |p, r| p.y - r.min.y,
+        |p, r| r.max.y - p.y,
+    ];
+
+    let mut output = poly.to_vec();
+
+    for distance in distances {
+        let input = std::mem::take(&mut output);
+
+        for (i, &current) in input.iter().enumerate() {
+            let previous = input[(i + input.len() - 1) % input.len()];
+            let current_distance = distance(current, rect);
+            let previous_distance = distance(previous, rect);
+
+            // the point on the edge, found by how far each end is from it
+            let crossing = || {
+                let t = previous_distance / (previous_distance - current_distance);
+                Point2D::new(previous.x + (current.x - previous.x) * t, previous.y + (current.y - previous.y) * t)
+            };
+
+            if current_distance >= 0.0 {
+                if previous_distance < 0.0 {
+                    output.push(crossing());
+                }
+                output.push(current);
+            } else if previous_distance >= 0.0 {
+                output.push(crossing());
+            }
+        }
+    }
+
+    output
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -40,23 +212,47 @@ impl Triangle2D {
     // if the edge function value is positive, the triangle vertices are
     // clockwise. otherwise, they are counterclockwise
     fn edge_function(a: Point2D, b: Point2D, c: Point2D) -> f64 {
-        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+        let ab = Point2D::new(b.x - a.x, b.y - a.y);
+        let ac = Point2D::new(c.x - a.x, c.y - a.y);
+
+        ab.perp_dot(ac)
     }
 
     pub fn signed_area(&self) -> f64 {
         2.0 * Triangle2D::edge_function(self.a, self.b, self.c)
     }
 
+    // the actual (unsigned) area, unlike signed_area which is only good for
+    // its sign
+    pub fn area(&self) -> f64 {
+        (Triangle2D::edge_function(self.a, self.b, self.c) / 2.0).abs()
+    }
+
+    // below this area (in [0, 1] screen units) a triangle is treated as a line
+    // or point, far smaller than a pixel even at 8K
+    pub const DEGENERATE_AREA: f64 = 1e-12;
+
+    // triangles with (nearly) no area can't have barycentric weights, see
+    // get_weights_at
+    pub fn is_degenerate(&self, eps: f64) -> bool {
+        self.area() <= eps
+    }
+
     // see https://jtsorlinis.github.io/rendering-tutorial/
-    // checks whether or not a point is inside the triangle
+    // checks whether or not a point is inside the triangle. degenerate
+    // triangles contain nothing
     pub fn contains_point(&self, p: Point2D) -> bool {
-        let (weight_a, weight_b, weight_c) = self.get_weights_at(p);
-
-        weight_a >= 0.0 && weight_b >= 0.0 && weight_c >= 0.0
+        self.get_weights_at(p)
+            .is_some_and(|(weight_a, weight_b, weight_c)| weight_a >= 0.0 && weight_b >= 0.0 && weight_c >= 0.0)
     }
 
-    // gets the 'weights' of each point (a,b,c) at a given point
-    pub fn get_weights_at(&self, p: Point2D) -> (f64, f64, f64) {
+    // gets the 'weights' of each point (a,b,c) at a given point, or None if
+    // the triangle is degenerate and they'd divide by (nearly) zero
+    pub fn get_weights_at(&self, p: Point2D) -> Option<(f64, f64, f64)> {
+        if self.is_degenerate(Triangle2D::DEGENERATE_AREA) {
+            return None;
+        }
+
         let abc = Triangle2D::edge_function(self.a, self.b, self.c);
         let abp = Triangle2D::edge_function(self.a, self.b, p);
         let bcp = Triangle2D::edge_function(self.b, self.c, p);
@@ -66,7 +262,19 @@ impl Triangle2D {
         let weight_b = cap / abc;
         let weight_c = abp / abc;
 
-        (weight_a, weight_b, weight_c)
+        Some((weight_a, weight_b, weight_c))
+    }
+
+    // how much each weight changes when the point moves dx to the right. like
+    // get_weights_at, only meaningful for triangles that aren't degenerate
+    pub fn weight_steps_x(&self, dx: f64) -> (f64, f64, f64) {
+        let abc = Triangle2D::edge_function(self.a, self.b, self.c);
+
+        (
+            (self.b.y - self.c.y) * dx / abc,
+            (self.c.y - self.a.y) * dx / abc,
+            (self.a.y - self.b.y) * dx / abc,
+        )
     }
 
     // returns two Ranges indicating the 'bounding box' of the triangle
@@ -91,10 +299,72 @@ impl Triangle2D {
         (min_x..max_x, min_y..max_y)
     }
 
+    // finds the columns of pixel row y (in a width x height buffer) whose
+    // sample points can be inside the triangle, by intersecting the row with
+    // each edge. the span is padded by a pixel on either side to absorb
+    // rounding, so callers still run contains_point but skip the empty parts
+    // of the bounding box
+    pub fn rasterize_scanline(&self, y: u32, width: u32, height: u32) -> Range<u32> {
+        let abc = Triangle2D::edge_function(self.a, self.b, self.c);
+        if abc == 0.0 {
+            return 0..0;
+        }
+
+        let py = y as f64 / height as f64;
+        let (mut lo, mut hi) = (f64::NEG_INFINITY, f64::INFINITY);
+
+        // each edge function is linear in x along the row: c - d * x, and
+        // has to have the same sign as abc for a point to be inside
+        for (p0, p1) in [(self.b, self.c), (self.c, self.a), (self.a, self.b)] {
+            let d = (p1.y - p0.y) * abc.signum();
+            let c = ((p1.x - p0.x) * (py - p0.y) + (p1.y - p0.y) * p0.x) * abc.signum();
+
+            if d > 0.0 {
+                hi = f64::min(hi, c / d);
+            } else if d < 0.0 {
+                lo = f64::max(lo, c / d);
+            } else if c < 0.0 {
+                return 0..0;
+            }
+        }
+
+        if lo > hi {
+            return 0..0;
+        }
+
+        let start = (lo * width as f64).ceil() - 1.0;
+        let end = (hi * width as f64).floor() + 2.0;
+
+        (f64::clamp(start, 0.0, width as f64) as u32)..(f64::clamp(end, 0.0, width as f64) as u32)
+    }
+
+    // the pixels of a width x height buffer whose sample points are inside
+    // the triangle, row by row, without painting anything. either winding
+    // counts
+    pub fn coverage(&self, width: u32, height: u32) -> Vec<(u32, u32)> {
+        let min_y = f64::min(f64::min(self.a.y, self.b.y), self.c.y);
+        let max_y = f64::max(f64::max(self.a.y, self.b.y), self.c.y);
+
+        let start_y = f64::clamp((min_y * height as f64).floor(), 0.0, height as f64) as u32;
+        let end_y = f64::clamp((max_y * height as f64).floor() + 1.0, 0.0, height as f64) as u32;
+
+        let mut pixels = Vec::new();
+
+        for y in start_y..end_y {
+            for x in self.rasterize_scanline(y, width, height) {
+                if self.contains_point(Point2D::new(x as f64 / width as f64, y as f64 / height as f64)) {
+                    pixels.push((x, y));
+                }
+            }
+        }
+
+        pixels
+    }
+
     // paints the triangle into a PaintBuffer object
     pub fn paint_to_buffer(&self, buffer: &mut PaintBuffer, paint_value: u32) {
-        // don't even bother with back-facing triangles
-        if self.signed_area() <= 0.0 {
+        // don't even bother with back-facing or degenerate triangles
+        if self.signed_area() <= 0.0 || self.is_degenerate(Triangle2D::DEGENERATE_AREA) {
             return;
         }
 
@@ -120,7 +390,7 @@ impl Triangle2D {
    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Point3D {
     pub x: f64,
     pub y: f64,
@@ -132,10 +402,25 @@ impl Point3D {
         Self { x, y, z }
     }
 
+    pub fn from_array(p: [f64; 3]) -> Self {
+        Self::new(p[0], p[1], p[2])
+    }
+
+    pub fn to_array(&self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+
     pub fn translated_by(&self, offset: Point3D) -> Self {
         Point3D::new(self.x + offset.x, self.y + offset.y, self.z + offset.z)
     }
 
+    // checks whether all components are within eps of the other point's
+    pub fn approx_eq(&self, other: Point3D, eps: f64) -> bool {
+        (self.x - other.x).abs() <= eps
+            && (self.y - other.y).abs() <= eps
+            && (self.z - other.z).abs() <= eps
+    }
+
     pub fn project_to_2d(&self) -> Point2D {
         // TODO: use FOV
         Point2D::new(
@@ -144,8 +429,28 @@ impl Point3D {
         )
     }
 
+    // projects the point to whole pixel coordinates in a width x height
+    // buffer, along with its depth from the camera. points behind the near
+    // plane have no pixel
+    pub fn project_to_screen(&self, camera: Camera, width: u32, height: u32) -> (Option<(i32, i32)>, f64) {
+        let depth = camera.depth_of(*self);
+        if depth <= 0.0 || depth < camera.near {
+            return (None, depth);
+        }
+
+        let p = camera.screen_transform().transform_point(*self);
+        let x = p.x * width as f64;
+        let y = p.y * height as f64;
+
+        (Some((x.floor() as i32, y.floor() as i32)), depth)
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
     pub fn normalized(&self) -> Self {
-        let magnitude = (self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        let magnitude = self.magnitude();
 
         Point3D::new(self.x / magnitude, self.y / magnitude, self.z / magnitude)
     }
@@ -154,10 +459,48 @@ impl Point3D {
         self.x * p.x + self.y * p.y + self.z * p.z
     }
 
+    pub fn cross(&self, p: Point3D) -> Self {
+        Point3D::new(
+            self.y * p.z - self.z * p.y,
+            self.z * p.x - self.x * p.z,
+            self.x * p.y - self.y * p.x,
+        )
+    }
+
     pub fn get_translating_point(&self) -> Self {
         Self::new(-self.x, -self.y, -self.z)
     }
 
+    // angle (in radians) between this vector and another one. the cosine is
+    // clamped since rounding can push it slightly outside of [-1, 1]
+    pub fn angle_between(&self, other: Point3D) -> f64 {
+        let cos_theta = self.dot(other) / (self.magnitude() * other.magnitude());
+
+        f64::clamp(cos_theta, -1.0, 1.0).acos()
+    }
+
+    // reflects this vector about the given (unit) normal: v - 2 * (v . n) * n
+    pub fn reflect(&self, normal: Point3D) -> Self {
+        let d = 2.0 * self.dot(normal);
+
+        Point3D::new(self.x - d * normal.x, self.y - d * normal.y, self.z - d * normal.z)
+    }
+
+    // component-wise minimum of two points
+    pub fn min(a: Point3D, b: Point3D) -> Self {
+        Point3D::new(f64::min(a.x, b.x), f64::min(a.y, b.y), f64::min(a.z, b.z))
+    }
+
+    // component-wise maximum of two points
+    pub fn max(a: Point3D, b: Point3D) -> Self {
+        Point3D::new(f64::max(a.x, b.x), f64::max(a.y, b.y), f64::max(a.z, b.z))
+    }
+
+    // clamps each component into the matching range of min and max
+    pub fn clamp(&self, min: Point3D, max: Point3D) -> Self {
+        Point3D::max(min, Point3D::min(*self, max))
+    }
+
     pub fn rotated_xz(&self, rotation: f64) -> Self {
         let magnitude = (self.x.powf(2.0) + self.z.powf(2.0)).sqrt();
         let theta = self.z.atan2(self.x) + rotation;
@@ -170,6 +513,18 @@ impl Point3D {
     }
 }
 
+impl From<[f32; 3]> for Point3D {
+    fn from(p: [f32; 3]) -> Self {
+        Self::new(p[0] as f64, p[1] as f64, p[2] as f64)
+    }
+}
+
+impl From<[f64; 3]> for Point3D {
+    fn from(p: [f64; 3]) -> Self {
+        Self::from_array(p)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Triangle3D {
     pub a: Point3D,
@@ -190,6 +545,55 @@ impl Triangle3D {
         }
     }
 
+    pub fn approx_eq(&self, other: Triangle3D, eps: f64) -> bool {
+        self.a.approx_eq(other.a, eps) && self.b.approx_eq(other.b, eps) && self.c.approx_eq(other.c, eps)
+    }
+
+    // unit normal of the front face, (b - a) x (c - a). degenerate triangles
+    // have no direction, so they get a zero vector
+    pub fn face_normal(&self) -> Point3D {
+        let edge_1 = self.b.translated_by(self.a.get_translating_point());
+        let edge_2 = self.c.translated_by(self.a.get_translating_point());
+        let normal = edge_1.cross(edge_2);
+
+        if normal.magnitude() > 0.0 { normal.normalized() } else { normal }
+    }
+
+    // see https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+    // returns the distance along `dir` (in multiples of its length) at which
+    // a ray from `origin` hits the triangle, if it does. both faces count
+    pub fn intersect_ray(&self, origin: Point3D, dir: Point3D) -> Option<f64> {
+        let eps = 1e-9;
+
+        let edge_1 = self.b.translated_by(self.a.get_translating_point());
+        let edge_2 = self.c.translated_by(self.a.get_translating_point());
+
+        let p = dir.cross(edge_2);
+        let det = edge_1.dot(p);
+        if det.abs() < eps {
+            return None; // ray is parallel to the triangle
+        }
+
+        let t_vec = origin.translated_by(self.a.get_translating_point());
+        let u = t_vec.dot(p) / det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = t_vec.cross(edge_1);
+        let v = dir.dot(q) / det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge_2.dot(q) / det;
+        if t > eps { Some(t) } else { None }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::new(self.a, self.a).including(self.b).including(self.c)
+    }
+
     pub fn project_to_2d(&self) -> Triangle2D {
         Triangle2D::new(
             self.a.project_to_2d(),
@@ -198,46 +602,180 @@ impl Triangle3D {
         )
     }
 
-    pub fn paint_to_buffer<ColorF: Fn(f64, f64, f64) -> u32>(&self, buffer: &mut PaintBuffer, scene: Scene, color_f: ColorF) {
-        let Scene(camera, light) = scene;
-        let mut translated_triangle = self.translated_by(camera.position.get_translating_point());
-        translated_triangle.a.y *= -1.0;
-        translated_triangle.b.y *= -1.0;
-        translated_triangle.c.y *= -1.0;
-        let projected_triangle = translated_triangle.project_to_2d();
-        let projected_triangle = projected_triangle.translated_by(Point2D::new(0.5, 0.5));
+    // applies a transform to each vertex, see Matrix4x4::transform_point
+    pub fn transformed(&self, m: &Matrix4x4) -> Self {
+        Self {
+            a: m.transform_point(self.a),
+            b: m.transform_point(self.b),
+            c: m.transform_point(self.c),
+        }
+    }
+
+    // projects the triangle onto the screen as seen by the camera, where
+    // (0, 0) is the top left and (1, 1) the bottom right
+    pub fn to_screen(&self, camera: Camera) -> Triangle2D {
+        self.project_with(&camera.screen_transform())
+    }
+
+    // same as to_screen, with the camera's screen transform already built
+    pub fn project_with(&self, screen_transform: &Matrix4x4) -> Triangle2D {
+        let projected = self.transformed(screen_transform);
+
+        Triangle2D::new(
+            Point2D::new(projected.a.x, projected.a.y),
+            Point2D::new(projected.b.x, projected.b.y),
+            Point2D::new(projected.c.x, projected.c.y),
+        )
+    }
+
+    // projects the triangle into a viewport of the buffer, see Viewport::from_unit
+    pub fn to_viewport(&self, screen_transform: &Matrix4x4, viewport: Viewport) -> Triangle2D {
+        let projected_triangle = self.project_with(screen_transform);
+
+        Triangle2D::new(
+            viewport.from_unit(projected_triangle.a),
+            viewport.from_unit(projected_triangle.b),
+            viewport.from_unit(projected_triangle.c),
+        )
+    }
+
+    // color_f gets the barycentric weights of a fragment and returns its
+    // packed color, along with the unclamped linear color for the HDR buffer
+    pub fn paint_to_buffer<ColorF: Fn(f64, f64, f64) -> (u32, (f64, f64, f64))>(&self, buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings, color_f: ColorF) {
+        self.rasterize(buffer.width, buffer.height, scene, settings, |index, (weight_a, weight_b, weight_c), z_val| {
+            // rasterize indexes the whole frame, which a band doesn't start at
+            let index = index - (buffer.first_row * buffer.width) as usize;
+
+            // counted whether or not the depth test passes
+            if let Some(overdraw) = &mut buffer.overdraw {
+                overdraw[index] += 1;
+            }
+
+            if settings.depth_func.passes(z_val, buffer.z_buffer[index], settings.depth_bias) {
+                if settings.depth_write {
+                    buffer.z_buffer[index] = z_val;
+                }
+
+                let (color, linear) = color_f(weight_a, weight_b, weight_c);
+                buffer.pixel_buffer[index] = color;
+
+                if let Some(hdr_buffer) = &mut buffer.hdr_buffer {
+                    hdr_buffer[index] = linear;
+                }
+            }
+        });
+    }
+
+    // finds every pixel of a width x height buffer the triangle covers,
+    // within the settings' pixel bounds and between the near and far planes.
+    // each one is passed to `fragment` as its index in the buffer, its
+    // barycentric weights and its depth, without any depth testing
+    pub fn rasterize<FragmentF: FnMut(usize, (f64, f64, f64), f64)>(&self, width: u32, height: u32, scene: Scene, settings: RenderSettings, mut fragment: FragmentF) {
+        let Scene(camera, _, screen_transform) = scene;
+        let projected_triangle = self.to_viewport(&screen_transform, settings.viewport);
        
-        // don't even bother with back-facing triangles
-        if projected_triangle.signed_area() <= 0.0 {
+        // don't even bother with back-facing triangles, unless they're drawn
+        if settings.cull_back_faces && !settings.front_face.is_front(projected_triangle) {
             return;
         }
 
-        let (range_x, range_y) = projected_triangle.get_bounding_box_px(buffer.width, buffer.height);
+        // edge-on triangles have no weights, see get_weights_at
+        if projected_triangle.is_degenerate(Triangle2D::DEGENERATE_AREA) {
+            return;
+        }
 
-        for y in range_y {
-            for x in range_x.clone() {
-                let index = (x + y * buffer.width) as usize;
+        // only the part of the triangle that's in the viewport gets
+        // rasterized, so every pixel visited is inside the buffer
+        let clipped = clip_to_rect(&[projected_triangle.a, projected_triangle.b, projected_triangle.c], settings.viewport.rect);
+        if clipped.is_empty() {
+            return;
+        }
 
-                if index >= buffer.pixel_buffer.len() {
-                    continue;
-                }
+        let min_x = clipped.iter().map(|p| p.x).fold(f64::MAX, f64::min);
+        let max_x = clipped.iter().map(|p| p.x).fold(f64::MIN, f64::max);
+        let min_y = clipped.iter().map(|p| p.y).fold(f64::MAX, f64::min);
+        let max_y = clipped.iter().map(|p| p.y).fold(f64::MIN, f64::max);
 
-                let x = (x as f64) / (buffer.width as f64);
-                let y = (y as f64) / (buffer.height as f64);
-                let p = Point2D::new(x, y);
+        let (viewport_x, viewport_y) = settings.pixel_bounds(width, height);
+        let range_x = u32::max((min_x * width as f64).floor() as u32, viewport_x.start)..u32::min((max_x * width as f64).ceil() as u32, viewport_x.end);
+        let range_y = u32::max((min_y * height as f64).floor() as u32, viewport_y.start)..u32::min((max_y * height as f64).ceil() as u32, viewport_y.end);
 
-                if projected_triangle.contains_point(p) {
-                    let (weight_a, weight_b, weight_c) = projected_triangle.get_weights_at(p);
-                    let z_val = self.a.z * weight_a + self.b.z * weight_b + self.c.z * weight_c;
+        // depth is measured from the camera, so it can be checked against the
+        // near and far planes
+        let depth_a = camera.depth_of(self.a);
+        let depth_b = camera.depth_of(self.b);
+        let depth_c = camera.depth_of(self.c);
 
-                    if z_val < buffer.z_buffer[index] {
-                        buffer.z_buffer[index] = z_val;
-                        buffer.pixel_buffer[index] = color_f(weight_a, weight_b, weight_c);
+        let (step_a, step_b, step_c) = projected_triangle.weight_steps_x(1.0 / width as f64);
+
+        let mut shade_fragment = |index: usize, (weight_a, weight_b, weight_c): (f64, f64, f64)| {
+            let z_val = depth_a * weight_a + depth_b * weight_b + depth_c * weight_c;
+
+            if z_val < camera.near || z_val > camera.far {
+                return;
+            }
+
+            fragment(index, (weight_a, weight_b, weight_c), z_val);
+        };
+
+        for y in range_y {
+            let row_x = match settings.rasterizer {
+                Rasterizer::BoundingBox => range_x.clone(),
+                Rasterizer::Scanline => {
+                    let span = projected_triangle.rasterize_scanline(y, width, height);
+                    u32::max(span.start, range_x.start)..u32::min(span.end, range_x.end)
+                }
+            };
+
+            // the weights are linear in x, so each pixel only costs a multiply
+            // and add from the left edge of the row instead of three edge
+            // functions. stepping from x = 0 rather than the start of the span
+            // keeps both rasterizers' weights identical
+            let Some((row_a, row_b, row_c)) = projected_triangle.get_weights_at(Point2D::new(0.0, y as f64 / height as f64)) else {
+                return;
+            };
+
+            for x_start in row_x.clone().step_by(4) {
+                let (weights, inside) = weights_x4((row_a, row_b, row_c), (step_a, step_b, step_c), x_start);
+
+                for lane in 0..4 {
+                    let x = x_start + lane;
+                    if x >= row_x.end || inside & (1 << lane) == 0 {
+                        continue;
                     }
+
+                    let lane = lane as usize;
+                    shade_fragment((x + y * width) as usize, (weights[0][lane], weights[1][lane], weights[2][lane]));
                 }
             }
         }
- 
+    }
+
+    // draws the edges of the triangle in the wireframe color on top of
+    // whatever is already in the buffer, skipping pixels more than the
+    // wireframe depth bias behind the z-buffer
+    pub fn paint_edges(&self, buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings) {
+        let Scene(camera, _, screen_transform) = scene;
+        let projected_triangle = self.to_viewport(&screen_transform, settings.viewport);
+
+        // the edges of back-facing triangles are hidden along with them
+        if settings.cull_back_faces && !settings.front_face.is_front(projected_triangle) {
+            return;
+        }
+
+        let depth_a = camera.depth_of(self.a);
+        let depth_b = camera.depth_of(self.b);
+        let depth_c = camera.depth_of(self.c);
+
+        let edges = [
+            (projected_triangle.a, projected_triangle.b, depth_a, depth_b),
+            (projected_triangle.b, projected_triangle.c, depth_b, depth_c),
+            (projected_triangle.c, projected_triangle.a, depth_c, depth_a),
+        ];
+
+        for (from, to, from_z, to_z) in edges {
+            buffer.draw_line(from, to, (from_z, to_z), settings.wireframe_color, settings.wireframe_depth_bias, settings.pixel_bounds(buffer.width, buffer.height));
+        }
     }
 
     pub fn rotated_xz(&self, rotation: f64) -> Self {
@@ -249,156 +787,3186 @@ impl Triangle3D {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct ColorTriangle {
-    pub color: u32,
-    pub tri: Triangle3D,
-    pub normal_tri: Triangle3D,
-}
+// barycentric weights of the 4 pixels starting at column x in a row, given
+// the row's weights at x = 0 and the step per pixel (see
+// Triangle3D::paint_to_buffer), plus a bitmask of the ones inside
+#[cfg(feature = "simd")]
+fn weights_x4(row: (f64, f64, f64), step: (f64, f64, f64), x: u32) -> ([[f64; 4]; 3], u32) {
+    use wide::f64x4;
 
-impl ColorTriangle {
-    pub fn new(color: u32, tri: Triangle3D, normal_tri: Triangle3D) -> Self {
-        ColorTriangle { color, tri, normal_tri }
-    }
+    let x = x as f64;
+    let xs = f64x4::new([x, x + 1.0, x + 2.0, x + 3.0]);
 
-    pub fn paint_to_buffer(&self, buffer: &mut PaintBuffer, scene: Scene) {
-        let Scene(camera, light) = scene;
+    // no fused multiply-add, so the weights match the scalar version exactly
+    let weight_a = f64x4::splat(row.0) + xs * f64x4::splat(step.0);
+    let weight_b = f64x4::splat(row.1) + xs * f64x4::splat(step.1);
+    let weight_c = f64x4::splat(row.2) + xs * f64x4::splat(step.2);
 
-        let light_dir_a = Point3D::new(
-            -self.tri.a.x + light.position.x,
-            -self.tri.a.y + light.position.y,
-            -self.tri.a.z + light.position.z,
-        ).normalized();
+    let inside = weight_a.simd_ge(f64x4::ZERO) & weight_b.simd_ge(f64x4::ZERO) & weight_c.simd_ge(f64x4::ZERO);
 
-        let light_dir_b = Point3D::new(
-            -self.tri.b.x + light.position.x,
-            -self.tri.b.y + light.position.y,
-            -self.tri.b.z + light.position.z,
-        ).normalized();
+    ([weight_a.to_array(), weight_b.to_array(), weight_c.to_array()], inside.to_bitmask())
+}
 
-        let light_dir_c = Point3D::new(
-            -self.tri.c.x + light.position.x,
-            -self.tri.c.y + light.position.y,
-            -self.tri.c.z + light.position.z,
-        ).normalized();
-        
-        let diff_brightness_a = light_dir_a.x * self.normal_tri.a.x + light_dir_a.y * self.normal_tri.a.y + light_dir_a.z * self.normal_tri.a.z;
-        let diff_brightness_b = light_dir_b.x * self.normal_tri.b.x + light_dir_b.y * self.normal_tri.b.y + light_dir_b.z * self.normal_tri.b.z;
-        let diff_brightness_c = light_dir_c.x * self.normal_tri.c.x + light_dir_c.y * self.normal_tri.c.y + light_dir_c.z * self.normal_tri.c.z;
+#[cfg(not(feature = "simd"))]
+fn weights_x4(row: (f64, f64, f64), step: (f64, f64, f64), x: u32) -> ([[f64; 4]; 3], u32) {
+    weights_x4_scalar(row, step, x)
+}
 
-        let halfway_dir_a = light_dir_a.translated_by(camera.view_dir).normalized();
-        let halfway_dir_b = light_dir_b.translated_by(camera.view_dir).normalized();
-        let halfway_dir_c = light_dir_c.translated_by(camera.view_dir).normalized();
+// weights_x4 one pixel at a time, which the simd version is checked against
+#[cfg(any(test, not(feature = "simd")))]
+fn weights_x4_scalar(row: (f64, f64, f64), step: (f64, f64, f64), x: u32) -> ([[f64; 4]; 3], u32) {
+    let mut weights = [[0.0; 4]; 3];
+    let mut inside = 0;
 
-        let spec_constant = 4.0;
-        let spec_brightness_a = f64::max(self.normal_tri.a.dot(halfway_dir_a), 0.0).powf(spec_constant);
-        let spec_brightness_b = f64::max(self.normal_tri.b.dot(halfway_dir_b), 0.0).powf(spec_constant);
-        let spec_brightness_c = f64::max(self.normal_tri.c.dot(halfway_dir_c), 0.0).powf(spec_constant);
+    for lane in 0..4 {
+        let x = (x + lane) as f64;
+        let (weight_a, weight_b, weight_c) = (row.0 + x * step.0, row.1 + x * step.1, row.2 + x * step.2);
 
-        self.tri.paint_to_buffer(buffer, scene, |weight_a, weight_b, weight_c| {
+        weights[0][lane as usize] = weight_a;
+        weights[1][lane as usize] = weight_b;
+        weights[2][lane as usize] = weight_c;
 
-            let mut brightness = 0.15; // ambient
-            brightness += diff_brightness_a * weight_a + diff_brightness_b * weight_b + diff_brightness_c * weight_c; // diffuse
-            brightness += spec_brightness_a * weight_a + spec_brightness_b * weight_b + spec_brightness_c * weight_c; // specular
-            brightness = f64::clamp(brightness, 0.0, 1.0);
+        if weight_a >= 0.0 && weight_b >= 0.0 && weight_c >= 0.0 {
+            inside |= 1 << lane;
+        }
+    }
 
-            let brightness_r = brightness * light.color.0;
-            let brightness_g = brightness * light.color.1;
-            let brightness_b = brightness * light.color.2;
+    (weights, inside)
+}
 
-            let r = (255.0 * brightness_r) as u32;
-            let g = (255.0 * brightness_g) as u32;
-            let b = (255.0 * brightness_b) as u32;
+// axis-aligned bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Point3D,
+    pub max: Point3D,
+}
 
-            (r << 16) | (g << 8) | b
-        });
+impl Aabb {
+    pub fn new(min: Point3D, max: Point3D) -> Self {
+        Self { min, max }
     }
 
-    pub fn translated_by(&self, offset: Point3D) -> Self {
+    // grows the box to include a point
+    pub fn including(&self, p: Point3D) -> Self {
         Self {
-            tri: self.tri.translated_by(offset),
-            normal_tri: self.normal_tri,
-            color: self.color,
+            min: Point3D::min(self.min, p),
+            max: Point3D::max(self.max, p),
         }
     }
+
+    // the smallest box containing both boxes
+    pub fn merge(&self, other: Aabb) -> Self {
+        self.including(other.min).including(other.max)
+    }
+
+    pub fn contains(&self, p: Point3D) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x
+            && p.y >= self.min.y && p.y <= self.max.y
+            && p.z >= self.min.z && p.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    pub fn center(&self) -> Point3D {
+        Point3D::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
 }
 
-pub struct PaintBuffer {
-    pub width: u32,
-    pub height: u32,
-    pub z_buffer: Vec<f64>,
-    pub pixel_buffer: Vec<u32>,
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpecularModel {
+    // uses the halfway vector between the light and view directions
+    #[default]
+    BlinnPhong,
+    // uses the light direction reflected about the normal
+    Phong,
 }
 
-impl PaintBuffer {
-    pub fn new(width: u32, height: u32) -> Self {
-        let buffer_size = (width * height) as usize;
+impl SpecularModel {
+    // computes the specular brightness at a point with the given normal
+    pub fn brightness(&self, normal: Point3D, light_dir: Point3D, view_dir: Point3D, exponent: f64) -> f64 {
+        let alignment = match self {
+            SpecularModel::BlinnPhong => normal.dot(light_dir.translated_by(view_dir).normalized()),
+            SpecularModel::Phong => light_dir.get_translating_point().reflect(normal).dot(view_dir),
+        };
 
-        Self {
-            width,
-            height,
-            z_buffer: vec![f64::MAX; buffer_size],
-            pixel_buffer: vec![0; buffer_size],
-        }
+        f64::max(alignment, 0.0).powf(exponent)
+    }
+}
+
+// how strongly a surface responds to each lighting term. the defaults
+// reproduce the plain RenderSettings lighting
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    // multiplies RenderSettings::ambient
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    // specular exponent, None uses RenderSettings::specular_exponent
+    pub shininess: Option<f64>,
+    // 0x00RRGGBB glow added on top of the lighting, regardless of lights
+    pub emissive: u32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            ambient: 1.0,
+            diffuse: 1.0,
+            specular: 1.0,
+            shininess: None,
+            emissive: 0x000000,
+        }
     }
 }
 
 #[derive(Clone, Copy, Debug)]
-pub struct Camera {
-    pub position: Point3D,
-    pub view_dir: Point3D,
+pub struct ColorTriangle {
+    pub color: u32,
+    pub tri: Triangle3D,
+    pub normal_tri: Triangle3D,
+    pub material: Material,
+    // texture coordinates of each vertex, if the mesh has them
+    pub uv_tri: Option<Triangle2D>,
 }
 
-impl Camera {
-    pub fn new(position: Point3D, view_dir: Point3D) -> Self {
-        Self { position, view_dir }
+impl ColorTriangle {
+    pub fn new(color: u32, tri: Triangle3D, normal_tri: Triangle3D) -> Self {
+        ColorTriangle { color, tri, normal_tri, material: Material::default(), uv_tri: None }
+    }
+
+    // writes `id` and the interpolated normal and position of the triangle
+    // into every pixel of the G-buffer where it passes the depth test
+    pub fn paint_to_gbuffer(&self, gbuffer: &mut GBuffer, scene: Scene, settings: RenderSettings, id: (usize, usize)) {
+        self.tri.rasterize(gbuffer.width, gbuffer.height, scene, settings, |index, (weight_a, weight_b, weight_c), z_val| {
+            if !settings.depth_func.passes(z_val, gbuffer.z_buffer[index], settings.depth_bias) {
+                return;
+            }
+
+            if settings.depth_write {
+                gbuffer.z_buffer[index] = z_val;
+            }
+
+            let interpolate = |t: Triangle3D| Point3D::new(
+                t.a.x * weight_a + t.b.x * weight_b + t.c.x * weight_c,
+                t.a.y * weight_a + t.b.y * weight_b + t.c.y * weight_c,
+                t.a.z * weight_a + t.b.z * weight_b + t.c.z * weight_c,
+            );
+
+            let normal = interpolate(self.normal_tri);
+
+            gbuffer.ids[index] = Some(id);
+            gbuffer.normals[index] = if normal.magnitude() > 0.0 { normal.normalized() } else { normal };
+            gbuffer.positions[index] = interpolate(self.tri);
+        });
+    }
+
+    // the texture, if any, replaces the flat color wherever the triangle has
+    // texture coordinates
+    pub fn paint_to_buffer(&self, buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings, shadow_map: Option<&ShadowMap>, texture: Option<&Texture>) {
+        let Scene(camera, light, screen_transform) = scene;
+
+        // back faces are lit as if they were facing the camera, so the inside
+        // of an open mesh isn't black
+        let normal_tri = if settings.two_sided_lighting && !settings.front_face.is_front(self.tri.project_with(&screen_transform)) {
+            Triangle3D::new(
+                self.normal_tri.a.get_translating_point(),
+                self.normal_tri.b.get_translating_point(),
+                self.normal_tri.c.get_translating_point(),
+            )
+        } else {
+            self.normal_tri
+        };
+
+        let light_dir_a = Point3D::new(
+            -self.tri.a.x + light.position.x,
+            -self.tri.a.y + light.position.y,
+            -self.tri.a.z + light.position.z,
+        ).normalized();
+
+        let light_dir_b = Point3D::new(
+            -self.tri.b.x + light.position.x,
+            -self.tri.b.y + light.position.y,
+            -self.tri.b.z + light.position.z,
+        ).normalized();
+
+        let light_dir_c = Point3D::new(
+            -self.tri.c.x + light.position.x,
+            -self.tri.c.y + light.position.y,
+            -self.tri.c.z + light.position.z,
+        ).normalized();
+        
+        let attenuation_a = light.attenuation(light_dir_a);
+        let attenuation_b = light.attenuation(light_dir_b);
+        let attenuation_c = light.attenuation(light_dir_c);
+
+        let diff_brightness_a = attenuation_a * (light_dir_a.x * normal_tri.a.x + light_dir_a.y * normal_tri.a.y + light_dir_a.z * normal_tri.a.z);
+        let diff_brightness_b = attenuation_b * (light_dir_b.x * normal_tri.b.x + light_dir_b.y * normal_tri.b.y + light_dir_b.z * normal_tri.b.z);
+        let diff_brightness_c = attenuation_c * (light_dir_c.x * normal_tri.c.x + light_dir_c.y * normal_tri.c.y + light_dir_c.z * normal_tri.c.z);
+
+        // direction from each vertex towards the camera
+        let view_dir_a = camera.position.translated_by(self.tri.a.get_translating_point()).normalized();
+        let view_dir_b = camera.position.translated_by(self.tri.b.get_translating_point()).normalized();
+        let view_dir_c = camera.position.translated_by(self.tri.c.get_translating_point()).normalized();
+
+        let spec_constant = self.material.shininess.unwrap_or(settings.specular_exponent);
+        let spec_brightness_a = attenuation_a * settings.specular_model.brightness(normal_tri.a, light_dir_a, view_dir_a, spec_constant);
+        let spec_brightness_b = attenuation_b * settings.specular_model.brightness(normal_tri.b, light_dir_b, view_dir_b, spec_constant);
+        let spec_brightness_c = attenuation_c * settings.specular_model.brightness(normal_tri.c, light_dir_c, view_dir_c, spec_constant);
+
+        let surface = unpack_color(self.color);
+        let (emissive_r, emissive_g, emissive_b) = unpack_color(self.material.emissive);
+
+        self.tri.paint_to_buffer(buffer, scene, settings, |weight_a, weight_b, weight_c| {
+
+            // points hidden from the light only get ambient lighting
+            let shadowed = shadow_map.is_some_and(|shadow_map| {
+                let p = Point3D::new(
+                    self.tri.a.x * weight_a + self.tri.b.x * weight_b + self.tri.c.x * weight_c,
+                    self.tri.a.y * weight_a + self.tri.b.y * weight_b + self.tri.c.y * weight_c,
+                    self.tri.a.z * weight_a + self.tri.b.z * weight_b + self.tri.c.z * weight_c,
+                );
+
+                shadow_map.is_shadowed(p)
+            });
+
+            let (surface_r, surface_g, surface_b) = match (texture, self.uv_tri) {
+                (Some(texture), Some(uv)) => unpack_color(texture.sample(
+                    uv.a.x * weight_a + uv.b.x * weight_b + uv.c.x * weight_c,
+                    uv.a.y * weight_a + uv.b.y * weight_b + uv.c.y * weight_c,
+                )),
+                _ => surface,
+            };
+
+            let material = self.material;
+            let mut diffuse = material.ambient * settings.ambient; // ambient
+            let mut specular = 0.0;
+            if !shadowed {
+                diffuse += material.diffuse * (diff_brightness_a * weight_a + diff_brightness_b * weight_b + diff_brightness_c * weight_c); // diffuse
+                specular = material.specular * (spec_brightness_a * weight_a + spec_brightness_b * weight_b + spec_brightness_c * weight_c); // specular
+            }
+            diffuse *= settings.exposure;
+            specular *= settings.exposure;
+
+            // the specular share of the lighting shifts its color from the
+            // light's towards the specular color
+            let brightness = diffuse + specular;
+            let specular_share = if brightness > 0.0 { specular / brightness } else { 0.0 };
+            let tint = |brightness: f64| (
+                brightness * light.color.0 + brightness * specular_share * (light.specular_color.0 - light.color.0),
+                brightness * light.color.1 + brightness * specular_share * (light.specular_color.1 - light.color.1),
+                brightness * light.color.2 + brightness * specular_share * (light.specular_color.2 - light.color.2),
+            );
+
+            // the HDR buffer keeps brightness above 1.0
+            let (linear_r, linear_g, linear_b) = tint(f64::max(brightness, 0.0));
+            let linear = (
+                linear_r * surface_r + emissive_r,
+                linear_g * surface_g + emissive_g,
+                linear_b * surface_b + emissive_b,
+            );
+
+            let (lit_r, lit_g, lit_b) = tint(f64::clamp(brightness, 0.0, 1.0));
+
+            // emission is added after lighting, so it shows even in shadow
+            let brightness_r = lit_r * surface_r + emissive_r;
+            let brightness_g = lit_g * surface_g + emissive_g;
+            let brightness_b = lit_b * surface_b + emissive_b;
+
+            (pack_color((brightness_r, brightness_g, brightness_b)), linear)
+        });
     }
 
+    pub fn translated_by(&self, offset: Point3D) -> Self {
+        Self {
+            tri: self.tri.translated_by(offset),
+            normal_tri: self.normal_tri,
+            color: self.color,
+            material: self.material,
+            uv_tri: self.uv_tri,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Light {
-    pub position: Point3D,
-    pub color: (f64, f64, f64),
+// fills the settings' pixel bounds of the buffer with whatever of the cube
+// map is seen through each pixel's center, as a backdrop for the scene. the
+// z-buffer is left alone so everything drawn afterwards covers it
+pub fn render_skybox(buffer: &mut PaintBuffer, camera: Camera, settings: RenderSettings, cube_map: &CubeMap) {
+    let (range_x, range_y) = settings.pixel_bounds(buffer.width, buffer.height);
+
+    for y in range_y {
+        for x in range_x.clone() {
+            let p = Point2D::new((x as f64 + 0.5) / buffer.width as f64, (y as f64 + 0.5) / buffer.height as f64);
+            let ndc = Camera::screen_to_ndc(settings.viewport.to_unit(p));
+
+            let color = cube_map.sample(camera.view_ray(ndc.x, ndc.y));
+            let index = buffer.index_of(x, y);
+            buffer.pixel_buffer[index] = color;
+
+            if let Some(hdr_buffer) = &mut buffer.hdr_buffer {
+                hdr_buffer[index] = unpack_color(color);
+            }
+        }
+    }
 }
 
-impl Light {
-    pub fn new(position: Point3D, color: (f64, f64, f64)) -> Self {
-        Self { position, color }
+// draws the x, y and z axes as red, green and blue lines from the world
+// origin, `length` long, depth tested against the scene like wireframe
+// edges. the parts behind the near plane are cut off
+pub fn render_axes(buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings, length: f64) {
+    let Scene(camera, _, screen_transform) = scene;
+    let near = f64::max(camera.near, 1e-6);
+    let origin = Point3D::new(0.0, 0.0, 0.0);
+
+    let axes = [
+        (Point3D::new(length, 0.0, 0.0), 0xFF0000),
+        (Point3D::new(0.0, length, 0.0), 0x00FF00),
+        (Point3D::new(0.0, 0.0, length), 0x0000FF),
+    ];
+
+    for (end, color) in axes {
+        let (mut from, mut to) = (origin, end);
+        let (depth_from, depth_to) = (camera.depth_of(from), camera.depth_of(to));
+
+        if depth_from < near && depth_to < near {
+            continue;
+        }
+
+        // slides whichever end is behind the near plane up onto it
+        let on_near = |t: f64| Point3D::new(t * end.x, t * end.y, t * end.z);
+        if depth_from < near {
+            from = on_near((near - depth_from) / (depth_to - depth_from));
+        } else if depth_to < near {
+            to = on_near((near - depth_from) / (depth_to - depth_from));
+        }
+
+        let project = |p: Point3D| {
+            let projected = screen_transform.transform_point(p);
+            settings.viewport.from_unit(Point2D::new(projected.x, projected.y))
+        };
+
+        buffer.draw_line(project(from), project(to), (camera.depth_of(from), camera.depth_of(to)), color, settings.wireframe_depth_bias, settings.pixel_bounds(buffer.width, buffer.height));
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct Scene(Camera, Light);
+// paints each point as a size x size pixel square of `color`, depth tested
+// like triangles so points hide each other and the rest of the scene.
+// points outside the near and far planes are skipped
+pub fn render_points(buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings, points: &[Point3D], color: u32, size: u32) {
+    let Scene(camera, _, screen_transform) = scene;
+    let (viewport_x, viewport_y) = settings.pixel_bounds(buffer.width, buffer.height);
+
+    for point in points {
+        let z_val = camera.depth_of(*point);
+        if z_val < camera.near || z_val > camera.far {
+            continue;
+        }
 
-impl Scene {
-    pub fn new(camera: Camera, light: Light) -> Self {
-        Self(camera, light)
+        let projected = screen_transform.transform_point(*point);
+        let center = settings.viewport.from_unit(Point2D::new(projected.x, projected.y));
+
+        // the square's top left corner, so odd sizes are centered on the point
+        let left = (center.x * buffer.width as f64 - size as f64 / 2.0).round();
+        let top = (center.y * buffer.height as f64 - size as f64 / 2.0).round();
+
+        let range_x = (f64::clamp(left, viewport_x.start as f64, viewport_x.end as f64) as u32)..(f64::clamp(left + size as f64, viewport_x.start as f64, viewport_x.end as f64) as u32);
+        let range_y = (f64::clamp(top, viewport_y.start as f64, viewport_y.end as f64) as u32)..(f64::clamp(top + size as f64, viewport_y.start as f64, viewport_y.end as f64) as u32);
+
+        for y in range_y {
+            for x in range_x.clone() {
+                let index = buffer.index_of(x, y);
+
+                if settings.depth_func.passes(z_val, buffer.z_buffer[index], settings.depth_bias) {
+                    if settings.depth_write {
+                        buffer.z_buffer[index] = z_val;
+                    }
+
+                    buffer.pixel_buffer[index] = color;
+
+                    if let Some(hdr_buffer) = &mut buffer.hdr_buffer {
+                        hdr_buffer[index] = unpack_color(color);
+                    }
+                }
+            }
+        }
     }
 }
 
-pub struct Object3D {
-    pub position: Point3D,
-    pub rotation: f64,
-    pub triangles: Vec<ColorTriangle>,
+// operators for squeezing HDR brightness into [0, 1], see
+// PaintBuffer::tone_map
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMap {
+    // x / (1 + x)
+    Reinhard,
+    // see https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+    Aces,
 }
 
-impl Object3D {
-    pub fn new(triangles: Vec<ColorTriangle>) -> Self {
+impl ToneMap {
+    // maps a single linear channel value
+    pub fn apply(&self, x: f64) -> f64 {
+        let x = f64::max(x, 0.0);
+
+        match self {
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::Aces => f64::clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0),
+        }
+    }
+}
+
+// the color storage of a PaintBuffer, either its own or someone else's (like
+// a window's) so frames can be painted in place
+pub enum Pixels<'a> {
+    Owned(Vec<u32>),
+    Borrowed(&'a mut [u32]),
+}
+
+impl Deref for Pixels<'_> {
+    type Target = [u32];
+
+    fn deref(&self) -> &[u32] {
+        match self {
+            Pixels::Owned(pixels) => pixels,
+            Pixels::Borrowed(pixels) => pixels,
+        }
+    }
+}
+
+impl DerefMut for Pixels<'_> {
+    fn deref_mut(&mut self) -> &mut [u32] {
+        match self {
+            Pixels::Owned(pixels) => pixels,
+            Pixels::Borrowed(pixels) => pixels,
+        }
+    }
+}
+
+pub struct PaintBuffer<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub z_buffer: Vec<f64>,
+    pub pixel_buffer: Pixels<'a>,
+    // how many fragments were depth tested at each pixel, only counted once
+    // enable_overdraw has been called
+    pub overdraw: Option<Vec<u32>>,
+    // unclamped linear (r, g, b) of each pixel, only written once enable_hdr
+    // has been called
+    pub hdr_buffer: Option<Vec<(f64, f64, f64)>>,
+    // the frame row the buffer starts at. usually 0, but a band (see
+    // PaintBuffer::band) only holds some of the rows of its frame
+    pub first_row: u32,
+}
+
+impl<'a> PaintBuffer<'a> {
+    pub fn new(width: u32, height: u32) -> Self {
+        let buffer_size = (width * height) as usize;
+
         Self {
-            position: Point3D::new(0.0, 0.0, 0.0),
-            rotation: 0.0,
-            triangles
+            width,
+            height,
+            z_buffer: vec![f64::MAX; buffer_size],
+            pixel_buffer: Pixels::Owned(vec![0; buffer_size]),
+            overdraw: None,
+            hdr_buffer: None,
+            first_row: 0,
         }
     }
 
-    pub fn paint_to_buffer(&self, buffer: &mut PaintBuffer, scene: Scene) {
-        for tri in &self.triangles {
-            let mut tri = tri.clone();
-            tri.tri = tri.tri.rotated_xz(self.rotation);
-            tri.normal_tri = tri.normal_tri.rotated_xz(self.rotation);
-            tri.tri = tri.tri.translated_by(self.position.get_translating_point());
-            tri.paint_to_buffer(buffer, scene);
+    // paints into existing width * height pixels instead of allocating them,
+    // only the z-buffer is allocated. the pixels are cleared to black like a
+    // new buffer's. returns None if the pixel count doesn't match
+    pub fn from_pixels(width: u32, height: u32, pixels: &'a mut [u32]) -> Option<Self> {
+        let buffer_size = (width * height) as usize;
+        if pixels.len() != buffer_size {
+            return None;
+        }
+
+        pixels.fill(0);
+
+        Some(Self {
+            width,
+            height,
+            z_buffer: vec![f64::MAX; buffer_size],
+            pixel_buffer: Pixels::Borrowed(pixels),
+            overdraw: None,
+            hdr_buffer: None,
+            first_row: 0,
+        })
+    }
+
+    // a buffer for just some rows of a width x height frame, painting into
+    // existing pixels that hold only those rows, so that a frame can be
+    // split between threads. unlike from_pixels the pixels are left as they
+    // are, and nothing outside the rows may be drawn to. returns None if the
+    // pixel count doesn't match
+    pub fn band(width: u32, height: u32, rows: Range<u32>, pixels: &'a mut [u32]) -> Option<Self> {
+        let buffer_size = (width * rows.len() as u32) as usize;
+        if pixels.len() != buffer_size || rows.end > height {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            z_buffer: vec![f64::MAX; buffer_size],
+            pixel_buffer: Pixels::Borrowed(pixels),
+            overdraw: None,
+            hdr_buffer: None,
+            first_row: rows.start,
+        })
+    }
+
+    // where pixel (x, y) of the frame is in the buffer's pixels and depths
+    pub fn index_of(&self, x: u32, y: u32) -> usize {
+        (x + (y - self.first_row) * self.width) as usize
+    }
+
+    // the frame rows the buffer holds, all of them unless it's a band
+    pub fn rows(&self) -> Range<u32> {
+        self.first_row..self.first_row + self.pixel_buffer.len() as u32 / u32::max(self.width, 1)
+    }
+
+    // stretches this buffer's pixels and depths over all of `target` with
+    // nearest neighbor sampling
+    pub fn upscale_to(&self, target: &mut PaintBuffer) {
+        for y in 0..target.height {
+            let source_y = y * self.height / target.height;
+
+            for x in 0..target.width {
+                let source_x = x * self.width / target.width;
+                let source = (source_x + source_y * self.width) as usize;
+                let index = (x + y * target.width) as usize;
+
+                target.pixel_buffer[index] = self.pixel_buffer[source];
+                target.z_buffer[index] = self.z_buffer[source];
+            }
+        }
+    }
+
+    // starts keeping the linear color of every pixel before it's clamped and
+    // quantized, for custom tone mapping
+    pub fn enable_hdr(&mut self) {
+        self.hdr_buffer = Some(vec![(0.0, 0.0, 0.0); self.pixel_buffer.len()]);
+    }
+
+    // starts counting fragments per pixel, see visualize_overdraw
+    pub fn enable_overdraw(&mut self) {
+        self.overdraw = Some(vec![0; self.pixel_buffer.len()]);
+    }
+
+    // rewrites the image from the HDR buffer through a tone mapping operator,
+    // so highlights roll off instead of clipping. does nothing if the HDR
+    // buffer isn't enabled
+    pub fn tone_map(&mut self, op: ToneMap) {
+        let Some(hdr_buffer) = &self.hdr_buffer else {
+            return;
+        };
+
+        for (pixel, &(r, g, b)) in self.pixel_buffer.iter_mut().zip(hdr_buffer) {
+            *pixel = pack_color((op.apply(r), op.apply(g), op.apply(b)));
+        }
+    }
+
+    // replaces the image with a heatmap of the overdraw counts: black for
+    // untouched pixels, then blue, cyan, green, yellow and red for 5 or more
+    // fragments. does nothing if overdraw isn't being counted
+    pub fn visualize_overdraw(&mut self) {
+        const RAMP: [u32; 6] = [0x000000, 0x0000FF, 0x00FFFF, 0x00FF00, 0xFFFF00, 0xFF0000];
+
+        let Some(overdraw) = &self.overdraw else {
+            return;
+        };
+
+        for (pixel, &count) in self.pixel_buffer.iter_mut().zip(overdraw) {
+            *pixel = RAMP[usize::min(count as usize, RAMP.len() - 1)];
+        }
+    }
+
+    // draws a line between two points on the screen ((0, 0) being the top left
+    // and (1, 1) the bottom right), depth tested against the z-buffer with
+    // some bias but without writing to it. only pixels in the given columns
+    // and rows (see RenderSettings::pixel_bounds) are drawn
+    pub fn draw_line(&mut self, from: Point2D, to: Point2D, (from_z, to_z): (f64, f64), color: u32, depth_bias: f64, (viewport_x, viewport_y): (Range<u32>, Range<u32>)) {
+
+        let from = Point2D::new(from.x * self.width as f64, from.y * self.height as f64);
+        let to = Point2D::new(to.x * self.width as f64, to.y * self.height as f64);
+
+        let steps = f64::max((to.x - from.x).abs(), (to.y - from.y).abs()).ceil().max(1.0);
+        // don't walk absurdly long lines from vertices projected near infinity
+        if !steps.is_finite() || steps > 16.0 * (self.width + self.height) as f64 {
+            return;
+        }
+
+        for i in 0..=(steps as u32) {
+            let t = i as f64 / steps;
+            let x = (from.x + (to.x - from.x) * t).round();
+            let y = (from.y + (to.y - from.y) * t).round();
+
+            if x < 0.0 || y < 0.0 || !viewport_x.contains(&(x as u32)) || !viewport_y.contains(&(y as u32)) {
+                continue;
+            }
+
+            let index = self.index_of(x as u32, y as u32);
+            let z_val = from_z + (to_z - from_z) * t;
+
+            if z_val - depth_bias <= self.z_buffer[index] {
+                self.pixel_buffer[index] = color;
+            }
+        }
+    }
+
+    // see https://en.wikipedia.org/wiki/Xiaolin_Wu%27s_line_algorithm
+    // draws an anti-aliased line between two points in [0, 1] screen
+    // coordinates, blending each pixel towards `color` by how much of it the
+    // line covers. there's no depth test, so this is meant for overlays like
+    // grids
+    pub fn draw_line_aa(&mut self, from: Point2D, to: Point2D, color: u32) {
+        let (mut x0, mut y0) = (from.x * self.width as f64, from.y * self.height as f64);
+        let (mut x1, mut y1) = (to.x * self.width as f64, to.y * self.height as f64);
+
+        // walk along whichever axis the line is longer in
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            (x0, y0, x1, y1) = (y0, x0, y1, x1);
+        }
+        if x0 > x1 {
+            (x0, y0, x1, y1) = (x1, y1, x0, y0);
+        }
+
+        // same guard as draw_line
+        if !(x1 - x0).is_finite() || x1 - x0 > 16.0 * (self.width + self.height) as f64 {
+            return;
+        }
+
+        let dx = x1 - x0;
+        let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+        let color = unpack_color(color);
+        let fract = |v: f64| v - v.floor();
+
+        let mut plot = |x: f64, y: f64, coverage: f64| {
+            let (x, y) = if steep { (y, x) } else { (x, y) };
+            self.blend_pixel(x as i64, y as i64, color, coverage);
+        };
+
+        // the endpoints only cover part of their column
+        let mut endpoint = |x: f64, y: f64, x_gap: f64| {
+            let x_end = x.round();
+            let y_end = y + gradient * (x_end - x);
+
+            plot(x_end, y_end.floor(), (1.0 - fract(y_end)) * x_gap);
+            plot(x_end, y_end.floor() + 1.0, fract(y_end) * x_gap);
+
+            (x_end, y_end)
+        };
+
+        let (x_start, y_start) = endpoint(x0, y0, 1.0 - fract(x0 + 0.5));
+        let (x_stop, _) = endpoint(x1, y1, fract(x1 + 0.5));
+
+        // each column in between is split between the two rows the line
+        // passes through
+        let mut y = y_start + gradient;
+        let mut x = x_start + 1.0;
+        while x < x_stop {
+            plot(x, y.floor(), 1.0 - fract(y));
+            plot(x, y.floor() + 1.0, fract(y));
+
+            y += gradient;
+            x += 1.0;
+        }
+    }
+
+    // mixes a pixel `coverage` of the way towards a linear color, ignoring
+    // pixels outside the buffer (or the rows of a band)
+    fn blend_pixel(&mut self, x: i64, y: i64, (r, g, b): (f64, f64, f64), coverage: f64) {
+        let rows = self.rows();
+        if x < 0 || x >= self.width as i64 || y < rows.start as i64 || y >= rows.end as i64 {
+            return;
+        }
+
+        let index = self.index_of(x as u32, y as u32);
+        let (old_r, old_g, old_b) = unpack_color(self.pixel_buffer[index]);
+
+        self.pixel_buffer[index] = pack_color((
+            old_r + (r - old_r) * coverage,
+            old_g + (g - old_g) * coverage,
+            old_b + (b - old_b) * coverage,
+        ));
+    }
+
+    // screen space ambient occlusion, see apply_ssao_rect
+    pub fn apply_ssao(&mut self, radius: u32, strength: f64) {
+        self.apply_ssao_rect(Rect::unit(), radius, strength);
+    }
+
+    // layers another buffer of the same size on top of this one. pixels
+    // that were drawn to in `other` replace this buffer's unless something
+    // nearer was drawn here, so the z-buffers decide the order. pixels
+    // `other` never drew to (its background) are left alone
+    pub fn composite_over(&mut self, other: &PaintBuffer) {
+        if (self.width, self.height) != (other.width, other.height) {
+            return;
+        }
+
+        for index in 0..self.pixel_buffer.len() {
+            let z_val = other.z_buffer[index];
+
+            if z_val != f64::MAX && z_val < self.z_buffer[index] {
+                self.z_buffer[index] = z_val;
+                self.pixel_buffer[index] = other.pixel_buffer[index];
+
+                if let (Some(hdr_buffer), Some(other_hdr)) = (&mut self.hdr_buffer, &other.hdr_buffer) {
+                    hdr_buffer[index] = other_hdr[index];
+                }
+            }
+        }
+    }
+
+    // see apply_ssao_pixels
+    pub fn apply_ssao_rect(&mut self, rect: Rect, radius: u32, strength: f64) {
+        let (range_x, range_y) = rect.pixel_range(self.width, self.height);
+        self.apply_ssao_pixels(range_x, range_y, radius, strength);
+    }
+
+    // darkens pixels in a range that sit in a crevice, found by looking at
+    // pairs of neighbors on opposite sides (up to `radius` pixels away): if
+    // the midpoint between them is noticeably closer to the camera than the
+    // pixel, the surface curves in around it. flat and sloped surfaces are
+    // left alone, and pairs that are much closer are a separate object in
+    // front so they don't count. strength is how dark a fully occluded pixel
+    // gets, from 0.0 (not at all) to 1.0
+    pub fn apply_ssao_pixels(&mut self, range_x: Range<u32>, range_y: Range<u32>, radius: u32, strength: f64) {
+        self.apply_ssao_sampling(range_x.clone(), range_y.clone(), range_x, range_y, radius, strength);
+    }
+
+    // like apply_ssao_pixels, but the neighbors looked at can come from a
+    // larger sample range than the pixels being darkened. redrawing part of
+    // a frame samples past its edges so they come out as in the whole frame
+    pub fn apply_ssao_sampling(&mut self, range_x: Range<u32>, range_y: Range<u32>, sample_x: Range<u32>, sample_y: Range<u32>, radius: u32, strength: f64) {
+        // as fractions of the pixel's own depth
+        const MIN_DEPTH_DIFFERENCE: f64 = 0.005;
+        const MAX_DEPTH_DIFFERENCE: f64 = 0.25;
+        const DIRECTIONS: [(i64, i64); 4] = [(1, 0), (1, 1), (0, 1), (-1, 1)];
+
+        let radius = i64::max(radius as i64, 1);
+
+        let depth_at = |z_buffer: &[f64], x: i64, y: i64| {
+            let in_rect = x >= sample_x.start as i64 && y >= sample_y.start as i64 && x < sample_x.end as i64 && y < sample_y.end as i64;
+            let depth = if in_rect { z_buffer[(x + y * self.width as i64) as usize] } else { f64::MAX };
+
+            (depth != f64::MAX).then_some(depth)
+        };
+
+        for y in range_y.clone() {
+            for x in range_x.clone() {
+                let index = (x + y * self.width) as usize;
+                let Some(depth) = depth_at(&self.z_buffer, x as i64, y as i64) else {
+                    continue; // background
+                };
+
+                let mut samples = 0;
+                let mut occluded = 0;
+
+                for (dx, dy) in DIRECTIONS {
+                    // one pair halfway out and one at the full radius
+                    for distance in [(radius + 1) / 2, radius] {
+                        let before = depth_at(&self.z_buffer, x as i64 - dx * distance, y as i64 - dy * distance);
+                        let after = depth_at(&self.z_buffer, x as i64 + dx * distance, y as i64 + dy * distance);
+                        let (Some(before), Some(after)) = (before, after) else {
+                            continue;
+                        };
+
+                        samples += 1;
+
+                        let difference = depth - 0.5 * (before + after);
+                        if difference > MIN_DEPTH_DIFFERENCE * depth && difference < MAX_DEPTH_DIFFERENCE * depth {
+                            occluded += 1;
+                        }
+                    }
+                }
+
+                if samples == 0 {
+                    continue;
+                }
+
+                let factor = 1.0 - f64::clamp(strength, 0.0, 1.0) * occluded as f64 / samples as f64;
+
+                let (r, g, b) = unpack_color(self.pixel_buffer[index]);
+                self.pixel_buffer[index] = pack_color((r * factor, g * factor, b * factor));
+
+                if let Some(hdr_buffer) = &mut self.hdr_buffer {
+                    let (r, g, b) = hdr_buffer[index];
+                    hdr_buffer[index] = (r * factor, g * factor, b * factor);
+                }
+            }
+        }
+    }
+
+    // cheap screen space anti-aliasing loosely based on FXAA. pixels whose
+    // neighborhood has enough contrast in luminance get blended with the two
+    // neighbors across the edge they sit on, flat regions are left alone
+    pub fn apply_fxaa(&mut self) {
+        const EDGE_THRESHOLD: f64 = 0.125;
+        const EDGE_THRESHOLD_MIN: f64 = 0.0312;
+
+        let source = self.pixel_buffer.to_vec();
+        let (width, height) = (self.width as i64, self.height as i64);
+
+        let pixel_at = |x: i64, y: i64| {
+            let x = x.clamp(0, width - 1);
+            let y = y.clamp(0, height - 1);
+            source[(x + y * width) as usize]
+        };
+
+        let luma = |c: u32| {
+            let r = ((c >> 16) & 0xFF) as f64;
+            let g = ((c >> 8) & 0xFF) as f64;
+            let b = (c & 0xFF) as f64;
+            (0.299 * r + 0.587 * g + 0.114 * b) / 255.0
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let center = pixel_at(x, y);
+                let (north, south) = (pixel_at(x, y - 1), pixel_at(x, y + 1));
+                let (west, east) = (pixel_at(x - 1, y), pixel_at(x + 1, y));
+
+                let luma_m = luma(center);
+                let (luma_n, luma_s, luma_w, luma_e) = (luma(north), luma(south), luma(west), luma(east));
+
+                let luma_max = luma_m.max(luma_n).max(luma_s).max(luma_w).max(luma_e);
+                let luma_min = luma_m.min(luma_n).min(luma_s).min(luma_w).min(luma_e);
+                if luma_max - luma_min < f64::max(EDGE_THRESHOLD_MIN, luma_max * EDGE_THRESHOLD) {
+                    continue;
+                }
+
+                // a horizontal edge changes the most going up and down
+                let vertical_gradient = (luma_n + luma_s - 2.0 * luma_m).abs();
+                let horizontal_gradient = (luma_w + luma_e - 2.0 * luma_m).abs();
+                let (a, b) = if vertical_gradient >= horizontal_gradient { (north, south) } else { (west, east) };
+
+                let channel = |shift: u32| {
+                    let sum = 2 * ((center >> shift) & 0xFF) + ((a >> shift) & 0xFF) + ((b >> shift) & 0xFF);
+                    ((sum + 2) / 4) << shift
+                };
+
+                self.pixel_buffer[(x + y * width) as usize] = channel(16) | channel(8) | channel(0);
+            }
         }
     }
+
+    // resets the depth of every pixel and fills it with the background color
+    pub fn clear(&mut self, background: u32) {
+        self.clear_rect(Rect::unit(), background);
+    }
+
+    // same as clear, but only for the pixels inside a rect
+    pub fn clear_rect(&mut self, rect: Rect, background: u32) {
+        let (range_x, range_y) = rect.pixel_range(self.width, self.height);
+        self.clear_pixels(range_x, range_y, background);
+    }
+
+    // replaces every pixel with a gradient from `top` on the first row to
+    // `bottom` on the last, for a sky-like background. the depth is left alone
+    pub fn fill_vertical_gradient(&mut self, top: u32, bottom: u32) {
+        self.fill_vertical_gradient_pixels(0..self.width, 0..self.height, 0..self.height, top, bottom);
+    }
+
+    // same as fill_vertical_gradient, but only for the pixels in the given
+    // columns and rows. the gradient runs from the first to the last of the
+    // `span` rows, so filling part of it doesn't squash the whole gradient in
+    pub fn fill_vertical_gradient_pixels(&mut self, range_x: Range<u32>, range_y: Range<u32>, span: Range<u32>, top: u32, bottom: u32) {
+        let last = f64::max(span.len() as f64 - 1.0, 1.0);
+
+        for y in range_y {
+            let t = f64::clamp((y as f64 - span.start as f64) / last, 0.0, 1.0);
+            let color = lerp_color(top, bottom, t);
+            let row = self.index_of(range_x.start, y)..self.index_of(range_x.end, y);
+
+            self.pixel_buffer[row.clone()].fill(color);
+
+            if let Some(hdr_buffer) = &mut self.hdr_buffer {
+                hdr_buffer[row].fill(unpack_color(color));
+            }
+        }
+    }
+
+    // same as clear, but only for the pixels in the given columns and rows
+    pub fn clear_pixels(&mut self, range_x: Range<u32>, range_y: Range<u32>, background: u32) {
+        for y in range_y {
+            let row = self.index_of(range_x.start, y)..self.index_of(range_x.end, y);
+
+            self.z_buffer[row.clone()].fill(f64::MAX);
+            self.pixel_buffer[row.clone()].fill(background);
+
+            if let Some(overdraw) = &mut self.overdraw {
+                overdraw[row.clone()].fill(0);
+            }
+
+            if let Some(hdr_buffer) = &mut self.hdr_buffer {
+                hdr_buffer[row].fill(unpack_color(background));
+            }
+        }
+    }
+
+    // converts the 0x00RRGGBB pixel buffer into an RGBA image with full alpha
+    pub fn to_rgba_image(&self) -> image::RgbaImage {
+        image::RgbaImage::from_fn(self.width, self.height, |x, y| {
+            let pixel = self.pixel_buffer[(x + y * self.width) as usize];
+
+            let r = ((pixel >> 16) & 0xFF) as u8;
+            let g = ((pixel >> 8) & 0xFF) as u8;
+            let b = (pixel & 0xFF) as u8;
+
+            image::Rgba([r, g, b, 0xFF])
+        })
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> image::ImageResult<()> {
+        self.to_rgba_image().save_with_format(path, image::ImageFormat::Png)
+    }
+
+    // writes the pixel buffer out as a binary (P6) PPM file
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+        for pixel in self.pixel_buffer.iter() {
+            let r = ((pixel >> 16) & 0xFF) as u8;
+            let g = ((pixel >> 8) & 0xFF) as u8;
+            let b = (pixel & 0xFF) as u8;
+
+            writer.write_all(&[r, g, b])?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Camera {
+    pub position: Point3D,
+    // should stay unit length, prefer set_view_dir over assigning it
+    pub view_dir: Point3D,
+    pub fov_y_radians: f64,
+    pub aspect: f64,
+    pub near: f64,
+    #[serde(with = "crate::scene::infinity_as_null")]
+    pub far: f64,
+}
+
+impl Camera {
+    // the field of view `new` uses, where x/z and y/z in [-0.5, 0.5] fill the screen
+    pub const DEFAULT_FOV_Y: f64 = 0.9272952180016122; // 2 * atan(0.5)
+
+    pub fn new(position: Point3D, view_dir: Point3D) -> Self {
+        Self::perspective(position, view_dir, Self::DEFAULT_FOV_Y, 1.0, 0.0, f64::INFINITY)
+    }
+
+    pub fn perspective(position: Point3D, view_dir: Point3D, fov_y_radians: f64, aspect: f64, near: f64, far: f64) -> Self {
+        let mut camera = Self { position, view_dir, fov_y_radians, aspect, near, far };
+        camera.set_view_dir(view_dir);
+
+        camera
+    }
+
+    // points the camera along `view_dir`, normalized. a zero vector has no
+    // direction, so it leaves the camera as it was
+    pub fn set_view_dir(&mut self, view_dir: Point3D) {
+        if view_dir.magnitude() > 0.0 {
+            self.view_dir = view_dir.normalized();
+        }
+    }
+
+    // unit vector pointing to the right of the view direction
+    pub fn right(&self) -> Point3D {
+        orthonormal_basis(self.view_dir).0
+    }
+
+    // unit vector pointing up, perpendicular to the view direction and right
+    pub fn up(&self) -> Point3D {
+        orthonormal_basis(self.view_dir).1
+    }
+
+    // what x/z and y/z get multiplied by so the field of view spans [-0.5, 0.5]
+    pub fn projection_scale(&self) -> (f64, f64) {
+        let tan_half_fov = (self.fov_y_radians / 2.0).tan();
+
+        (0.5 / (tan_half_fov * self.aspect), 0.5 / tan_half_fov)
+    }
+
+    // moves world space into camera space, with the camera at the origin
+    // looking down +z and +y up. the rows are the right/up/forward basis, so
+    // turning the camera turns the world the opposite way
+    pub fn view_matrix(&self) -> Matrix4x4 {
+        let (right, up) = orthonormal_basis(self.view_dir);
+        let forward = self.view_dir;
+
+        let rotation = Matrix4x4::new([
+            [right.x, right.y, right.z, 0.0],
+            [up.x, up.y, up.z, 0.0],
+            [forward.x, forward.y, forward.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        rotation.multiplied_by(&Matrix4x4::translation(self.position.get_translating_point()))
+    }
+
+    // distance of a world space point in front of the camera, measured along
+    // the view direction. this is the z of the point in camera space
+    pub fn depth_of(&self, p: Point3D) -> f64 {
+        self.view_dir.dot(p.translated_by(self.position.get_translating_point()))
+    }
+
+    // unit world space direction of the ray leaving the camera through a
+    // point on the screen, in normalized device coordinates (-1 to 1, with +y
+    // pointing up). this is the inverse of the projection, so (0, 0) gives
+    // the view direction
+    pub fn view_ray(&self, ndc_x: f64, ndc_y: f64) -> Point3D {
+        let (scale_x, scale_y) = self.projection_scale();
+
+        self.to_world_dir(Point3D::new(0.5 * ndc_x / scale_x, 0.5 * ndc_y / scale_y, 1.0)).normalized()
+    }
+
+    // turns a camera space direction into a world space one, the inverse of
+    // the rotation in view_matrix
+    pub fn to_world_dir(&self, v: Point3D) -> Point3D {
+        let (right, up) = orthonormal_basis(self.view_dir);
+
+        Point3D::new(
+            v.x * right.x + v.y * up.x + v.z * self.view_dir.x,
+            v.x * right.y + v.y * up.y + v.z * self.view_dir.y,
+            v.x * right.z + v.y * up.z + v.z * self.view_dir.z,
+        )
+    }
+
+    // perspective projection of camera space, looking down +z, into normalized
+    // device coordinates: x and y in [-1, 1] across the field of view with +y
+    // up (same as view_ray), and z as 1 / depth
+    pub fn projection_matrix(&self) -> Matrix4x4 {
+        let (scale_x, scale_y) = self.projection_scale();
+
+        Matrix4x4::new([
+            [2.0 * scale_x, 0.0, 0.0, 0.0],
+            [0.0, 2.0 * scale_y, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    pub fn view_projection(&self) -> Matrix4x4 {
+        self.projection_matrix().multiplied_by(&self.view_matrix())
+    }
+
+    // where the origin of normalized device coordinates lands on the screen
+    pub const VIEWPORT_CENTER: Point2D = Point2D { x: 0.5, y: 0.5 };
+
+    // maps normalized device coordinates onto the screen, where (0, 0) is the
+    // top left and (1, 1) the bottom right. this is the only place y gets
+    // flipped to point down. normals never go through it
+    pub fn viewport_matrix() -> Matrix4x4 {
+        Matrix4x4::new([
+            [0.5, 0.0, 0.0, Camera::VIEWPORT_CENTER.x],
+            [0.0, -0.5, 0.0, Camera::VIEWPORT_CENTER.y],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // the inverse of viewport_matrix, for turning screen positions (like the
+    // cursor's) back into normalized device coordinates
+    pub fn screen_to_ndc(p: Point2D) -> Point2D {
+        let centered = p.sub(Camera::VIEWPORT_CENTER);
+
+        Point2D::new(2.0 * centered.x, -2.0 * centered.y)
+    }
+
+    // world space straight to [0, 1] screen coordinates
+    pub fn screen_transform(&self) -> Matrix4x4 {
+        Camera::viewport_matrix().multiplied_by(&self.view_projection())
+    }
+
+    // moves the camera back from the object's center, against the view
+    // direction, far enough for its bounding sphere to fit in both the vertical field of view `fov`
+    // (in radians) and the horizontal one implied by `aspect`
+    pub fn frame_object(&mut self, object: &Object3D, fov: f64, aspect: f64) {
+        let (center, radius) = object.bounding_sphere();
+
+        let horizontal_fov = 2.0 * ((fov / 2.0).tan() * aspect).atan();
+        let half_fov = f64::min(fov, horizontal_fov) / 2.0;
+        let distance = radius / half_fov.sin();
+
+        self.position = Point3D::new(
+            center.x - distance * self.view_dir.x,
+            center.y - distance * self.view_dir.y,
+            center.z - distance * self.view_dir.z,
+        );
+    }
+
+    // the volume visible to the camera, bounded by the field of view and the
+    // near and far planes. the planes are set up in camera space and then
+    // turned to face wherever the camera looks
+    pub fn frustum(&self) -> Frustum {
+        let (scale_x, scale_y) = self.projection_scale();
+        let (half_x, half_y) = (0.5 / scale_x, 0.5 / scale_y);
+
+        let camera_planes = [
+            (Point3D::new(-1.0, 0.0, half_x), 0.0),
+            (Point3D::new(1.0, 0.0, half_x), 0.0),
+            (Point3D::new(0.0, -1.0, half_y), 0.0),
+            (Point3D::new(0.0, 1.0, half_y), 0.0),
+            (Point3D::new(0.0, 0.0, 1.0), -self.near),
+            (Point3D::new(0.0, 0.0, -1.0), self.far),
+        ];
+
+        Frustum {
+            planes: camera_planes.map(|(n, d)| {
+                let n = self.to_world_dir(n);
+
+                (n, d - n.dot(self.position))
+            }),
+        }
+    }
+}
+
+// builds (right, up) unit vectors perpendicular to `forward` and each other,
+// with up as close to +y as possible. when forward is (nearly) vertical, +z
+// is used as the reference instead since +y can't be
+pub fn orthonormal_basis(forward: Point3D) -> (Point3D, Point3D) {
+    let forward = forward.normalized();
+
+    let world_up = if forward.y.abs() > 0.999 { Point3D::new(0.0, 0.0, 1.0) } else { Point3D::new(0.0, 1.0, 0.0) };
+    let right = world_up.cross(forward).normalized();
+    let up = forward.cross(right);
+
+    (right, up)
+}
+
+// a convex volume bounded by planes, where a point p is inside when
+// normal.dot(p) + d >= 0 for every (normal, d) plane
+#[derive(Clone, Copy, Debug)]
+pub struct Frustum {
+    pub planes: [(Point3D, f64); 6],
+}
+
+impl Frustum {
+    // conservative test, a box is only rejected when it is fully outside of
+    // one of the planes
+    pub fn intersects_aabb(&self, aabb: Aabb) -> bool {
+        self.planes.iter().all(|&(n, d)| {
+            // the corner furthest along the plane normal
+            let p = Point3D::new(
+                if n.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if n.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if n.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+
+            n.dot(p) + d >= 0.0
+        })
+    }
+
+    // moves the frustum from world space into an object's local space, the
+    // inverse of the transform in Object3D::to_world
+    pub fn to_object_space(&self, object: &Object3D) -> Self {
+        Self {
+            planes: self.planes.map(|(n, d)| (n.rotated_xz(-object.rotation), d - n.dot(object.position))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LightKind {
+    // shines equally in every direction from the light's position
+    Point,
+    // shines along `direction`, fading out between the inner and outer cone
+    // angles (in radians)
+    Spot {
+        direction: Point3D,
+        inner_angle: f64,
+        outer_angle: f64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Light {
+    pub position: Point3D,
+    // tints the ambient and diffuse lighting
+    pub color: (f64, f64, f64),
+    // tints the specular highlights, white unless changed
+    pub specular_color: (f64, f64, f64),
+    pub kind: LightKind,
+}
+
+impl Light {
+    pub fn new(position: Point3D, color: (f64, f64, f64)) -> Self {
+        Self { position, color, specular_color: (1.0, 1.0, 1.0), kind: LightKind::Point }
+    }
+
+    pub fn spot(position: Point3D, direction: Point3D, inner_angle: f64, outer_angle: f64, color: (f64, f64, f64)) -> Self {
+        Self {
+            position,
+            color,
+            specular_color: (1.0, 1.0, 1.0),
+            kind: LightKind::Spot { direction, inner_angle, outer_angle },
+        }
+    }
+
+    // how much of the light reaches a point, given the (normalized) direction
+    // from that point to the light. 1.0 is full intensity, 0.0 is none
+    pub fn attenuation(&self, light_dir: Point3D) -> f64 {
+        match self.kind {
+            LightKind::Point => 1.0,
+            LightKind::Spot { direction, inner_angle, outer_angle } => {
+                let angle = light_dir.get_translating_point().angle_between(direction);
+
+                if angle <= inner_angle {
+                    1.0
+                } else if angle >= outer_angle {
+                    0.0
+                } else {
+                    (outer_angle - angle) / (outer_angle - inner_angle)
+                }
+            }
+        }
+    }
+}
+
+// a circular path in the xz plane for animating a light, starting at
+// center + (radius, 0, 0) and going all the way around every `period` seconds.
+// a period that isn't above 0 keeps the light at the start
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightOrbit {
+    pub center: Point3D,
+    pub radius: f64,
+    pub period: f64,
+}
+
+impl LightOrbit {
+    pub fn new(center: Point3D, radius: f64, period: f64) -> Self {
+        Self { center, radius, period }
+    }
+
+    // where the light is `time` seconds in
+    pub fn position_at(&self, time: f64) -> Point3D {
+        // dividing by a zero period would give NaN
+        let angle = if self.period > 0.0 { 2.0 * PI * time / self.period } else { 0.0 };
+
+        self.center.translated_by(Point3D::new(self.radius * angle.cos(), 0.0, self.radius * angle.sin()))
+    }
+}
+
+// the camera's screen transform is built once here, rather than for every
+// triangle painted with the scene
+#[derive(Clone, Copy, Debug)]
+pub struct Scene(Camera, Light, Matrix4x4);
+
+impl Scene {
+    pub fn new(camera: Camera, light: Light) -> Self {
+        Self(camera, light, camera.screen_transform())
+    }
+}
+
+// which winding order, as seen on screen, counts as the front of a triangle.
+// back faces are culled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrontFace {
+    // what the OBJ loader produces for the bundled models
+    #[default]
+    Cw,
+    Ccw,
+}
+
+impl FrontFace {
+    // checks whether a projected triangle faces the camera. degenerate
+    // triangles never do
+    pub fn is_front(&self, projected: Triangle2D) -> bool {
+        match self {
+            FrontFace::Cw => projected.signed_area() > 0.0,
+            FrontFace::Ccw => projected.signed_area() < 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderMode {
+    #[default]
+    Shaded,
+    // shaded, with the visible triangle edges drawn on top
+    ShadedWireframe,
+    // unlit, each triangle filled with a color hashed from its index in the
+    // object, see triangle_id_color
+    TriangleId,
+    // only the edges that aren't hidden behind other triangles, in the
+    // wireframe color over the background. the triangles are filled with
+    // the background first so their depth hides the edges behind them
+    HiddenLine,
+}
+
+// converts a 0x00RRGGBB color into linear [0, 1] (r, g, b)
+pub fn unpack_color(color: u32) -> (f64, f64, f64) {
+    (
+        ((color >> 16) & 0xFF) as f64 / 255.0,
+        ((color >> 8) & 0xFF) as f64 / 255.0,
+        (color & 0xFF) as f64 / 255.0,
+    )
+}
+
+// converts a linear (r, g, b) color into the 0x00RRGGBB format, clamping
+// each channel to [0, 1]
+pub fn pack_color((r, g, b): (f64, f64, f64)) -> u32 {
+    let r = (255.0 * f64::clamp(r, 0.0, 1.0)) as u32;
+    let g = (255.0 * f64::clamp(g, 0.0, 1.0)) as u32;
+    let b = (255.0 * f64::clamp(b, 0.0, 1.0)) as u32;
+
+    (r << 16) | (g << 8) | b
+}
+
+// see https://prng.di.unimi.it/splitmix64.c
+// a stable, well scattered color for a triangle index, so neighbouring
+// triangles rarely end up looking alike
+pub fn triangle_id_color(id: usize) -> u32 {
+    let mut x = (id as u64).wrapping_add(0x9E3779B97F4A7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+    x ^= x >> 31;
+
+    (x as u32) & 0xFFFFFF
+}
+
+// how a fragment's depth is compared against the z-buffer to decide whether
+// it's drawn. the z-buffer starts out infinitely far away, so Greater only
+// draws over pixels something has already been drawn to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DepthFunc {
+    // nearer fragments win
+    #[default]
+    Less,
+    // nearer fragments win, and so do ties
+    LessEqual,
+    // farther fragments win, for drawing only what's behind something
+    Greater,
+    // no depth test
+    Always,
+}
+
+impl DepthFunc {
+    // a positive bias lets later fragments win ties in depth, whichever way
+    // the comparison goes
+    pub fn passes(&self, z_val: f64, stored: f64, bias: f64) -> bool {
+        match self {
+            DepthFunc::Less => z_val < stored + bias,
+            DepthFunc::LessEqual => z_val <= stored + bias,
+            DepthFunc::Greater => z_val > stored - bias,
+            DepthFunc::Always => true,
+        }
+    }
+}
+
+// how Triangle3D::paint_to_buffer finds the pixels to test. BoundingBox and
+// Scanline paint exactly the same pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rasterizer {
+    // tests every pixel in the triangle's bounding box
+    #[default]
+    BoundingBox,
+    // only tests the span of each row that crosses the triangle, which skips
+    // most of the box for thin or slanted triangles
+    Scanline,
+}
+
+// knobs for tuning how a frame is rendered
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RenderSettings {
+    pub ambient: f64,
+    pub specular_exponent: f64,
+    pub specular_model: SpecularModel,
+    pub background: u32,
+    // (top, bottom) colors of a vertical gradient drawn in place of the
+    // flat background, see PaintBuffer::fill_vertical_gradient
+    pub background_gradient: Option<(u32, u32)>,
+    pub depth_bias: f64,
+    pub depth_func: DepthFunc,
+    // fragments that pass the depth test only update the z-buffer when set.
+    // turning it off lets translucent geometry be depth tested against the
+    // rest of the scene without hiding itself
+    pub depth_write: bool,
+    pub shadows: bool,
+    pub shadow_map_size: u32,
+    pub shadow_bias: f64,
+    pub render_mode: RenderMode,
+    pub wireframe_color: u32,
+    pub wireframe_depth_bias: f64,
+    pub front_face: FrontFace,
+    // drawing back faces too is needed for open meshes, which can be seen
+    // from inside
+    pub cull_back_faces: bool,
+    // lights back faces with their normals flipped, see
+    // ColorTriangle::paint_to_buffer
+    pub two_sided_lighting: bool,
+    pub rasterizer: Rasterizer,
+    // multiplies the lighting before it's clamped (or tone mapped)
+    pub exposure: f64,
+    // the part of the buffer to draw into, in [0, 1] coordinates. see
+    // World::render_viewport
+    pub viewport: Viewport,
+    // screen space ambient occlusion, see PaintBuffer::apply_ssao_rect
+    pub ssao: bool,
+    pub ssao_radius: u32,
+    pub ssao_strength: f64,
+    // fraction of the output resolution (per axis) that's actually rendered,
+    // the result is upscaled with nearest neighbor. values above 1 count as 1
+    pub resolution_scale: f64,
+    // (x, y, width, height) in pixels. when set, only the pixels inside it
+    // are cleared and drawn to, the rest of the buffer is left alone
+    pub scissor: Option<(u32, u32, u32, u32)>,
+    // how many threads World::render_viewport splits a frame between, as
+    // bands of rows. None uses every core and Some(1) renders on the
+    // calling thread
+    pub threads: Option<usize>,
+    // draws the world axes on top of the scene, see render_axes
+    pub show_axes: bool,
+}
+
+impl RenderSettings {
+    // the number of threads to render with, at least 1
+    pub fn thread_count(&self) -> usize {
+        let threads = self.threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+
+        usize::max(threads, 1)
+    }
+
+    // the pixels of a width x height buffer that get drawn to: the
+    // viewport's, cut down to the scissor rect if there is one
+    pub fn pixel_bounds(&self, width: u32, height: u32) -> (Range<u32>, Range<u32>) {
+        let (range_x, range_y) = self.viewport.rect.pixel_range(width, height);
+
+        let Some((x, y, scissor_width, scissor_height)) = self.scissor else {
+            return (range_x, range_y);
+        };
+
+        let start_x = u32::max(range_x.start, x);
+        let start_y = u32::max(range_y.start, y);
+        let end_x = u32::max(u32::min(range_x.end, x.saturating_add(scissor_width)), start_x);
+        let end_y = u32::max(u32::min(range_y.end, y.saturating_add(scissor_height)), start_y);
+
+        (start_x..end_x, start_y..end_y)
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            ambient: 0.15,
+            specular_exponent: 4.0,
+            specular_model: SpecularModel::default(),
+            background: 0x111111,
+            background_gradient: None,
+            depth_bias: 0.0,
+            depth_func: DepthFunc::default(),
+            depth_write: true,
+            shadows: false,
+            shadow_map_size: 1024,
+            shadow_bias: 0.02,
+            render_mode: RenderMode::default(),
+            wireframe_color: 0x00FF00,
+            wireframe_depth_bias: 0.01,
+            front_face: FrontFace::default(),
+            cull_back_faces: true,
+            two_sided_lighting: false,
+            rasterizer: Rasterizer::default(),
+            exposure: 1.0,
+            viewport: Viewport::full(),
+            ssao: false,
+            ssao_radius: 6,
+            ssao_strength: 0.6,
+            resolution_scale: 1.0,
+            scissor: None,
+            threads: None,
+            show_axes: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NormalMode {
+    // each vertex gets the average of the face normals of every triangle
+    // sharing its position, for a rounded look
+    #[default]
+    Smooth,
+    // each vertex gets the normal of its own triangle, for a faceted look
+    Flat,
+}
+
+pub struct Object3D {
+    pub position: Point3D,
+    pub rotation: f64,
+    // radians per second that World::update turns the object by. 0 stops
+    // it where it is and negative values spin it the other way
+    pub rotation_speed: f64,
+    pub triangles: Vec<ColorTriangle>,
+    // only valid for the triangles it was built from, see build_bvh
+    pub bvh: Option<Bvh>,
+    // the triangles' shared vertices, so each is transformed once per frame.
+    // like bvh, only valid for the triangles it was built from, see
+    // build_mesh
+    pub mesh: Option<IndexedMesh>,
+    // sampled by triangles with texture coordinates
+    pub texture: Option<Texture>,
+}
+
+impl Object3D {
+    pub fn new(triangles: Vec<ColorTriangle>) -> Self {
+        Self {
+            position: Point3D::new(0.0, 0.0, 0.0),
+            rotation: 0.0,
+            rotation_speed: 1.0,
+            triangles,
+            bvh: None,
+            mesh: None,
+            texture: None,
+        }
+    }
+
+    // builds an object from an indexed mesh, with every triangle the same
+    // color. the mesh is kept for rendering
+    pub fn from_indexed(mesh: IndexedMesh, color: u32) -> Self {
+        let mut object = Self::new(mesh.to_triangles(color));
+        object.mesh = Some(mesh);
+
+        object
+    }
+
+    // builds an object from a triangle strip of (position, normal) vertices.
+    // every other triangle in a strip has its winding flipped, so those get
+    // their first two vertices swapped to keep them all facing the same way
+    pub fn from_strip(vertices: &[(Point3D, Point3D)], color: u32) -> Self {
+        let triangles = vertices
+            .windows(3)
+            .enumerate()
+            .map(|(i, v)| if i % 2 == 0 { (v[0], v[1], v[2]) } else { (v[1], v[0], v[2]) })
+            .map(|(a, b, c)| ColorTriangle::new(color, Triangle3D::new(a.0, b.0, c.0), Triangle3D::new(a.1, b.1, c.1)))
+            .collect::<Vec<ColorTriangle>>();
+
+        Self::new(triangles)
+    }
+
+    // builds an object from a triangle fan of (position, normal) vertices,
+    // where every triangle shares the first vertex
+    pub fn from_fan(vertices: &[(Point3D, Point3D)], color: u32) -> Self {
+        let Some(&center) = vertices.first() else {
+            return Self::new(Vec::new());
+        };
+
+        let triangles = vertices[1..]
+            .windows(2)
+            .map(|v| ColorTriangle::new(color, Triangle3D::new(center.0, v[0].0, v[1].0), Triangle3D::new(center.1, v[0].1, v[1].1)))
+            .collect::<Vec<ColorTriangle>>();
+
+        Self::new(triangles)
+    }
+
+    // bakes each object's rotation and position into its triangles and puts
+    // them all in one object, at the origin, so they can be drawn as a batch.
+    // an object only has one texture, so the first object's is kept and the
+    // texture coordinates of every other object are dropped. the bvh and
+    // mesh are rebuilt for the merged triangles if any of the objects had one
+    pub fn merge(objects: &[Object3D]) -> Self {
+        let texture = objects.first().and_then(|object| object.texture.clone());
+
+        let triangles = objects
+            .iter()
+            .enumerate()
+            .flat_map(|(i, object)| object.world_triangles().map(move |mut tri| {
+                if i > 0 {
+                    tri.uv_tri = None;
+                }
+
+                tri
+            }))
+            .collect::<Vec<ColorTriangle>>();
+
+        let mut merged = Self::new(triangles);
+        merged.texture = texture;
+
+        if objects.iter().any(|object| object.bvh.is_some()) {
+            merged.build_bvh();
+        }
+        if objects.iter().any(|object| object.mesh.is_some()) {
+            merged.build_mesh();
+        }
+
+        merged
+    }
+
+    // builds a bounding volume hierarchy used to cull off-screen triangles.
+    // it has to be rebuilt (or cleared) if the triangles change afterwards
+    pub fn build_bvh(&mut self) {
+        self.bvh = Some(Bvh::build(&self.triangles));
+    }
+
+    // indexes the triangles' vertices so rendering transforms each shared
+    // vertex once. it has to be rebuilt (or cleared) if the triangles change
+    // afterwards
+    pub fn build_mesh(&mut self) {
+        self.mesh = Some(IndexedMesh::from_triangles(&self.triangles));
+    }
+
+    // applies the object's rotation and position to a (position, normal)
+    // vertex, the same way to_world does to whole triangles
+    pub fn to_world_vertex(&self, (position, normal): (Point3D, Point3D)) -> (Point3D, Point3D) {
+        (
+            position.rotated_xz(self.rotation).translated_by(self.position.get_translating_point()),
+            normal.rotated_xz(self.rotation),
+        )
+    }
+
+    // applies the object's rotation and position to one of its triangles
+    pub fn to_world(&self, tri: &ColorTriangle) -> ColorTriangle {
+        let mut tri = *tri;
+        tri.tri = tri.tri.rotated_xz(self.rotation);
+        tri.normal_tri = tri.normal_tri.rotated_xz(self.rotation);
+        tri.tri = tri.tri.translated_by(self.position.get_translating_point());
+        tri
+    }
+
+    // gives every triangle of the object the same material
+    pub fn set_material(&mut self, material: Material) {
+        for tri in &mut self.triangles {
+            tri.material = material;
+        }
+    }
+
+    // the object's triangles with its rotation and position applied
+    pub fn world_triangles(&self) -> impl Iterator<Item = ColorTriangle> + '_ {
+        self.triangles.iter().map(|tri| self.to_world(tri))
+    }
+
+    // builds a size x size checkerboard in the xz plane (at y = 0), centered
+    // on the origin and split into divisions x divisions squares
+    pub fn ground_plane(size: f64, divisions: u32, color_a: u32, color_b: u32) -> Self {
+        let cell = size / divisions as f64;
+        let start = -size / 2.0;
+        let up = Point3D::new(0.0, 1.0, 0.0);
+        let normal_tri = Triangle3D::new(up, up, up);
+
+        let mut triangles = Vec::new();
+        for i in 0..divisions {
+            for j in 0..divisions {
+                let x0 = start + i as f64 * cell;
+                let z0 = start + j as f64 * cell;
+                let color = if (i + j) % 2 == 0 { color_a } else { color_b };
+
+                let p00 = Point3D::new(x0, 0.0, z0);
+                let p10 = Point3D::new(x0 + cell, 0.0, z0);
+                let p01 = Point3D::new(x0, 0.0, z0 + cell);
+                let p11 = Point3D::new(x0 + cell, 0.0, z0 + cell);
+
+                // wound so the top of the plane is the front
+                triangles.push(ColorTriangle::new(color, Triangle3D::new(p00, p01, p11), normal_tri));
+                triangles.push(ColorTriangle::new(color, Triangle3D::new(p00, p11, p10), normal_tri));
+            }
+        }
+
+        Self::new(triangles)
+    }
+
+    // translates the triangles so the average of their vertices sits at the
+    // origin, which makes the object rotate about its visual center
+    pub fn center_on_origin(&mut self) {
+        if self.triangles.is_empty() {
+            return;
+        }
+
+        let mut sum = Point3D::new(0.0, 0.0, 0.0);
+        for tri in &self.triangles {
+            sum = sum.translated_by(tri.tri.a).translated_by(tri.tri.b).translated_by(tri.tri.c);
+        }
+
+        let count = (self.triangles.len() * 3) as f64;
+        let centroid = Point3D::new(sum.x / count, sum.y / count, sum.z / count);
+
+        for tri in &mut self.triangles {
+            *tri = tri.translated_by(centroid.get_translating_point());
+        }
+
+        self.bvh = None;
+        self.mesh = None;
+    }
+
+    // replaces every vertex normal with one computed from the faces. see
+    // NormalMode for the difference between smooth and flat
+    pub fn recompute_normals(&mut self, mode: NormalMode) {
+        match mode {
+            NormalMode::Flat => {
+                for tri in &mut self.triangles {
+                    let face_normal = tri.tri.face_normal();
+                    tri.normal_tri = Triangle3D::new(face_normal, face_normal, face_normal);
+                }
+            }
+
+            NormalMode::Smooth => {
+                let smooth = self.smooth_normals();
+
+                for tri in &mut self.triangles {
+                    tri.normal_tri = Triangle3D::new(smooth(tri.tri.a), smooth(tri.tri.b), smooth(tri.tri.c));
+                }
+            }
+        }
+
+        // the mesh's vertices carry the old normals
+        self.mesh = None;
+    }
+
+    // below this length a vertex normal counts as missing, see
+    // fill_missing_normals
+    pub const MISSING_NORMAL_LENGTH: f64 = 1e-9;
+
+    // gives every vertex without a usable normal (e.g. one a file left out,
+    // or gave as zero) a smooth one, leaving the rest as they are. this is
+    // what the loaders use, so a mesh that only has normals on some of its
+    // faces doesn't end up with black ones
+    pub fn fill_missing_normals(&mut self) {
+        let smooth = self.smooth_normals();
+
+        for tri in &mut self.triangles {
+            let vertices = [tri.tri.a, tri.tri.b, tri.tri.c];
+            let normals = [&mut tri.normal_tri.a, &mut tri.normal_tri.b, &mut tri.normal_tri.c];
+
+            for (p, normal) in vertices.into_iter().zip(normals) {
+                if normal.magnitude() < Object3D::MISSING_NORMAL_LENGTH {
+                    *normal = smooth(p);
+                }
+            }
+        }
+
+        self.mesh = None;
+    }
+
+    // looks up the smooth normal of a vertex position: the normalized sum of
+    // the face normals of every triangle sharing it. positions only touched
+    // by degenerate triangles get a zero vector
+    fn smooth_normals(&self) -> impl Fn(Point3D) -> Point3D {
+        let key = |p: Point3D| [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+        let mut sums: HashMap<[u64; 3], Point3D> = HashMap::new();
+
+        for tri in &self.triangles {
+            let face_normal = tri.tri.face_normal();
+
+            for p in [tri.tri.a, tri.tri.b, tri.tri.c] {
+                let sum = sums.entry(key(p)).or_insert(Point3D::new(0.0, 0.0, 0.0));
+                *sum = sum.translated_by(face_normal);
+            }
+        }
+
+        move |p: Point3D| {
+            let sum = sums[&key(p)];
+            if sum.magnitude() > 0.0 { sum.normalized() } else { sum }
+        }
+    }
+
+    // returns the center and radius of a sphere enclosing every vertex of the
+    // object (with its rotation and position applied). the center is the
+    // middle of the vertices' bounding box
+    pub fn bounding_sphere(&self) -> (Point3D, f64) {
+        let vertices = self.world_triangles()
+            .flat_map(|tri| [tri.tri.a, tri.tri.b, tri.tri.c])
+            .collect::<Vec<Point3D>>();
+
+        let Some(&first) = vertices.first() else {
+            return (Point3D::new(0.0, 0.0, 0.0), 0.0);
+        };
+
+        let center = vertices
+            .iter()
+            .fold(Aabb::new(first, first), |aabb, &v| aabb.including(v))
+            .center();
+        let radius = vertices
+            .iter()
+            .map(|v| v.translated_by(center.get_translating_point()).magnitude())
+            .fold(0.0, f64::max);
+
+        (center, radius)
+    }
+
+    pub fn paint_to_buffer(&self, buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings, shadow_map: Option<&ShadowMap>) {
+        let triangles = self.visible_triangles(scene);
+
+        for (i, tri) in &triangles {
+            match settings.render_mode {
+                RenderMode::TriangleId => tri.tri.paint_to_buffer(buffer, scene, settings, |_, _, _| {
+                    let color = triangle_id_color(*i);
+                    (color, unpack_color(color))
+                }),
+                RenderMode::HiddenLine => tri.tri.paint_to_buffer(buffer, scene, settings, |_, _, _| {
+                    (settings.background, unpack_color(settings.background))
+                }),
+                _ => tri.paint_to_buffer(buffer, scene, settings, shadow_map, self.texture.as_ref()),
+            }
+        }
+
+        if matches!(settings.render_mode, RenderMode::ShadedWireframe | RenderMode::HiddenLine) {
+            for (_, tri) in &triangles {
+                tri.tri.paint_edges(buffer, scene, settings);
+            }
+        }
+    }
+
+    // fills the G-buffer with the object's visible triangles, tagged with
+    // `object_index` and their index in self.triangles
+    pub fn paint_to_gbuffer(&self, gbuffer: &mut GBuffer, scene: Scene, settings: RenderSettings, object_index: usize) {
+        for (i, tri) in self.visible_triangles(scene) {
+            tri.paint_to_gbuffer(gbuffer, scene, settings, (object_index, i));
+        }
+    }
+
+    // the triangles that might be on screen, in world space along with their
+    // index in self.triangles. without a bvh that's all of them
+    fn visible_triangles(&self, scene: Scene) -> Vec<(usize, ColorTriangle)> {
+        let Scene(camera, _, _) = scene;
+
+        let visible = match &self.bvh {
+            Some(bvh) => bvh.visible_triangles(&camera.frustum().to_object_space(self)),
+            None => (0..self.triangles.len()).collect(),
+        };
+
+        // with a mesh, the visible triangles are put together from vertices
+        // that are each only moved into world space once
+        let mut cache = self.mesh.as_ref().map(|mesh| VertexCache::new(self, mesh));
+
+        visible
+            .into_iter()
+            .map(|i| match &mut cache {
+                Some(cache) => {
+                    let mut tri = self.triangles[i];
+                    (tri.tri, tri.normal_tri) = cache.triangle(i);
+
+                    (i, tri)
+                }
+                None => (i, self.to_world(&self.triangles[i])),
+            })
+            .collect::<Vec<(usize, ColorTriangle)>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_rgba_image_keeps_channel_order() {
+        let mut buffer = PaintBuffer::new(2, 1);
+        buffer.pixel_buffer[0] = 0x123456;
+        buffer.pixel_buffer[1] = 0xFF0080;
+
+        let image = buffer.to_rgba_image();
+
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0).0, [0x12, 0x34, 0x56, 0xFF]);
+        assert_eq!(image.get_pixel(1, 0).0, [0xFF, 0x00, 0x80, 0xFF]);
+    }
+
+    #[test]
+    fn save_ppm_writes_header_and_pixels() {
+        let mut buffer = PaintBuffer::new(2, 2);
+        buffer.pixel_buffer.copy_from_slice(&[0xFF0000, 0x00FF00, 0x0000FF, 0x123456]);
+
+        let path = std::env::temp_dir().join(format!("rust_graphics_test_{}.ppm", std::process::id()));
+        buffer.save_ppm(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(&bytes[header.len()..], &[0xFF, 0, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn approx_eq_within_and_beyond_eps() {
+        let eps = 1e-6;
+
+        let p = Point2D::new(1.0, -2.0);
+        assert!(p.approx_eq(p, eps));
+        assert!(!p.approx_eq(Point2D::new(1.0 + 2.0 * eps, -2.0), eps));
+
+        let q = Point3D::new(1.0, -2.0, 3.0);
+        assert!(q.approx_eq(q, eps));
+        assert!(!q.approx_eq(Point3D::new(1.0, -2.0, 3.0 + 2.0 * eps), eps));
+
+        let tri = Triangle3D::new(q, Point3D::new(0.0, 1.0, 0.0), Point3D::new(4.0, 5.0, 6.0));
+        assert!(tri.approx_eq(tri, eps));
+        assert!(!tri.approx_eq(tri.translated_by(Point3D::new(2.0 * eps, 0.0, 0.0)), eps));
+    }
+
+    #[test]
+    fn reflect_off_up_normal_points_up() {
+        let down = Point3D::new(1.0, -1.0, 0.0);
+        let reflected = down.reflect(Point3D::new(0.0, 1.0, 0.0));
+
+        assert!(reflected.approx_eq(Point3D::new(1.0, 1.0, 0.0), 1e-12));
+    }
+
+    #[test]
+    fn angle_between_perpendicular_and_identical() {
+        let x = Point3D::new(2.0, 0.0, 0.0);
+        let y = Point3D::new(0.0, 3.0, 0.0);
+
+        assert!((x.angle_between(y) - PI / 2.0).abs() < 1e-12);
+        assert_eq!(x.angle_between(x), 0.0);
+    }
+
+    // a camera at the origin looking down +z, with the default field of view
+    fn test_camera() -> Camera {
+        Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0))
+    }
+
+    // a square at depth z facing test_camera, 2 * half wide and centered on
+    // the view axis
+    fn wall(z: f64, half: f64, color: u32) -> [ColorTriangle; 2] {
+        let normal = Point3D::new(0.0, 0.0, -1.0);
+        let normal_tri = Triangle3D::new(normal, normal, normal);
+
+        let top_left = Point3D::new(-half, half, z);
+        let top_right = Point3D::new(half, half, z);
+        let bottom_left = Point3D::new(-half, -half, z);
+        let bottom_right = Point3D::new(half, -half, z);
+
+        [
+            ColorTriangle::new(color, Triangle3D::new(top_left, top_right, bottom_left), normal_tri),
+            ColorTriangle::new(color, Triangle3D::new(top_right, bottom_right, bottom_left), normal_tri),
+        ]
+    }
+
+    fn paint_all(triangles: &[ColorTriangle], buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings) {
+        for tri in triangles {
+            tri.paint_to_buffer(buffer, scene, settings, None, None);
+        }
+    }
+
+    fn pixel(buffer: &PaintBuffer, x: u32, y: u32) -> u32 {
+        buffer.pixel_buffer[(x + y * buffer.width) as usize]
+    }
+
+    #[test]
+    fn spot_light_cone_falloff() {
+        let light = Light::spot(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0), 0.1, 0.2, (1.0, 1.0, 1.0));
+
+        assert_eq!(light.attenuation(Point3D::new(0.0, 0.0, -1.0)), 1.0);
+        assert_eq!(light.attenuation(Point3D::new(-1.0, 0.0, -1.0).normalized()), 0.0);
+
+        // lighting is per vertex, so each wall is small enough for all of its
+        // vertices to be on the same side of the cone. the one straight ahead
+        // is inside the inner cone, the one off to the right well outside the
+        // outer one
+        let inside = wall(5.0, 0.2, 0xFFFFFF);
+        let outside = wall(5.0, 0.2, 0xFFFFFF).map(|tri| tri.translated_by(Point3D::new(2.0, 0.0, 0.0)));
+        let mut triangles = [inside, outside].concat();
+        for tri in &mut triangles {
+            tri.material.specular = 0.0;
+        }
+
+        let settings = RenderSettings::default();
+        let mut buffer = PaintBuffer::new(64, 64);
+        paint_all(&triangles, &mut buffer, Scene::new(test_camera(), light), settings);
+
+        let ambient = pack_color((settings.ambient, settings.ambient, settings.ambient));
+        assert_eq!(pixel(&buffer, 32, 32), 0xFFFFFF);
+        assert_eq!(pixel(&buffer, 58, 32), ambient);
+    }
+
+    #[test]
+    fn point3d_array_round_trip() {
+        let p = Point3D::new(1.5, -2.0, 0.25);
+
+        assert_eq!(Point3D::from_array(p.to_array()), p);
+        assert_eq!(Point3D::from([1.5, -2.0, 0.25]), p);
+        assert_eq!(Point3D::from([1.5f32, -2.0, 0.25]), p);
+    }
+
+    #[test]
+    fn default_settings_match_old_constants() {
+        let settings = RenderSettings::default();
+
+        assert_eq!(settings.ambient, 0.15);
+        assert_eq!(settings.specular_exponent, 4.0);
+        assert_eq!(settings.background, 0x111111);
+    }
+
+    #[test]
+    fn strip_triangles_face_the_same_way() {
+        let normal = Point3D::new(0.0, 0.0, -1.0);
+        let vertices = [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0), (2.0, 0.0)]
+            .map(|(x, y)| (Point3D::new(x, y, 0.0), normal));
+
+        let object = Object3D::from_strip(&vertices, 0xFFFFFF);
+        assert_eq!(object.triangles.len(), 3);
+
+        let first = object.triangles[0].tri.face_normal();
+        assert!(first.magnitude() > 0.0);
+        for tri in &object.triangles {
+            assert!(tri.tri.face_normal().approx_eq(first, 1e-12));
+        }
+    }
+
+    #[test]
+    fn bounding_sphere_of_known_vertices() {
+        let up = Point3D::new(0.0, 1.0, 0.0);
+        let object = Object3D::new(vec![ColorTriangle::new(
+            0xFFFFFF,
+            Triangle3D::new(Point3D::new(-1.0, 0.0, 0.0), Point3D::new(1.0, 0.0, 0.0), Point3D::new(0.0, 2.0, 0.0)),
+            Triangle3D::new(up, up, up),
+        )]);
+
+        let (center, radius) = object.bounding_sphere();
+
+        assert!(center.approx_eq(Point3D::new(0.0, 1.0, 0.0), 1e-12));
+        assert!((radius - 2.0f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn frame_object_backs_off_further_for_bigger_objects() {
+        let distance_for = |size: f64| {
+            let object = crate::primitives::cube(size);
+            let mut camera = test_camera();
+            camera.frame_object(&object, camera.fov_y_radians, camera.aspect);
+
+            camera.position.magnitude()
+        };
+
+        assert!(distance_for(2.0) > distance_for(1.0));
+    }
+
+    #[test]
+    fn center_on_origin_moves_centroid_to_zero() {
+        let mut object = crate::primitives::cube(1.0);
+        for tri in &mut object.triangles {
+            *tri = tri.translated_by(Point3D::new(3.0, -2.0, 5.0));
+        }
+
+        object.center_on_origin();
+
+        let vertices = object.triangles.iter().flat_map(|tri| [tri.tri.a, tri.tri.b, tri.tri.c]).collect::<Vec<Point3D>>();
+        let sum = vertices.iter().fold(Point3D::new(0.0, 0.0, 0.0), |sum, &v| sum.translated_by(v));
+        let count = vertices.len() as f64;
+
+        assert!(Point3D::new(sum.x / count, sum.y / count, sum.z / count).approx_eq(Point3D::new(0.0, 0.0, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn aabb_merge_and_triangle_bounds() {
+        let a = Aabb::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(1.0, 1.0, 1.0));
+        let b = Aabb::new(Point3D::new(-1.0, 0.5, 2.0), Point3D::new(0.5, 3.0, 4.0));
+        let merged = a.merge(b);
+
+        assert_eq!(merged.min, Point3D::new(-1.0, 0.0, 0.0));
+        assert_eq!(merged.max, Point3D::new(1.0, 3.0, 4.0));
+
+        let tri = Triangle3D::new(Point3D::new(1.0, -2.0, 0.0), Point3D::new(-3.0, 4.0, 1.0), Point3D::new(0.0, 0.0, -5.0));
+        let bounds = tri.aabb();
+
+        assert_eq!(bounds.min, Point3D::new(-3.0, -2.0, -5.0));
+        assert_eq!(bounds.max, Point3D::new(1.0, 4.0, 1.0));
+    }
+
+    #[test]
+    fn intersect_ray_hit_and_miss() {
+        let [tri, _] = wall(5.0, 1.0, 0xFFFFFF);
+        let origin = Point3D::new(0.0, 0.0, 0.0);
+
+        let t = tri.tri.intersect_ray(origin, Point3D::new(-0.1, 0.1, 1.0)).unwrap();
+        assert!((t - 5.0).abs() < 1e-9);
+
+        assert_eq!(tri.tri.intersect_ray(origin, Point3D::new(0.5, -0.5, 1.0)), None);
+        assert_eq!(tri.tri.intersect_ray(origin, Point3D::new(0.0, 0.0, -1.0)), None);
+    }
+
+    #[test]
+    fn view_rays_spread_evenly_around_view_dir() {
+        let camera = Camera::new(Point3D::new(1.0, 2.0, 3.0), Point3D::new(1.0, -1.0, 2.0));
+
+        assert!(camera.view_ray(0.0, 0.0).approx_eq(camera.view_dir, 1e-12));
+
+        // opposite corners lean away from the view direction by the same
+        // angle, so their sum points straight along it
+        for (x, y) in [(1.0, 1.0), (1.0, -1.0)] {
+            let (corner, opposite) = (camera.view_ray(x, y), camera.view_ray(-x, -y));
+
+            assert!((corner.magnitude() - 1.0).abs() < 1e-12);
+            assert!(corner.angle_between(camera.view_dir) > 0.1);
+            assert!((corner.angle_between(camera.view_dir) - opposite.angle_between(camera.view_dir)).abs() < 1e-12);
+            assert!(corner.translated_by(opposite).normalized().approx_eq(camera.view_dir, 1e-12));
+        }
+    }
+
+    #[test]
+    fn camera_constructors() {
+        let position = Point3D::new(1.0, 2.0, 3.0);
+        let view_dir = Point3D::new(0.0, 0.0, 1.0);
+
+        let camera = Camera::perspective(position, view_dir, 1.2, 16.0 / 9.0, 0.1, 100.0);
+        assert_eq!(camera.position, position);
+        assert_eq!(camera.view_dir, view_dir);
+        assert_eq!(camera.fov_y_radians, 1.2);
+        assert_eq!(camera.aspect, 16.0 / 9.0);
+        assert_eq!(camera.near, 0.1);
+        assert_eq!(camera.far, 100.0);
+
+        let camera = Camera::new(position, view_dir);
+        assert_eq!(camera.fov_y_radians, Camera::DEFAULT_FOV_Y);
+        assert_eq!(camera.aspect, 1.0);
+        assert_eq!(camera.near, 0.0);
+        assert_eq!(camera.far, f64::INFINITY);
+    }
+
+    #[test]
+    fn camera_basis_is_orthonormal() {
+        let directions = [
+            Point3D::new(0.0, 0.0, 1.0),
+            Point3D::new(1.0, 0.5, -2.0),
+            Point3D::new(0.0, 1.0, 0.0),
+            Point3D::new(0.0, -1.0, 0.0),
+        ];
+
+        for view_dir in directions {
+            let camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), view_dir);
+            let (right, up) = (camera.right(), camera.up());
+
+            assert!(right.dot(up).abs() < 1e-12);
+            assert!(right.dot(camera.view_dir).abs() < 1e-12);
+            assert!(up.dot(camera.view_dir).abs() < 1e-12);
+            assert!((right.magnitude() - 1.0).abs() < 1e-12);
+            assert!((up.magnitude() - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn ground_plane_triangle_count() {
+        for divisions in [1, 2, 5] {
+            let plane = Object3D::ground_plane(4.0, divisions, 0xFFFFFF, 0x000000);
+
+            assert_eq!(plane.triangles.len(), (2 * divisions * divisions) as usize);
+        }
+    }
+
+    // paints the triangles as one object into a cleared 64x64 buffer, seen
+    // by test_camera and lit from behind it
+    fn render_triangles(triangles: Vec<ColorTriangle>, settings: RenderSettings) -> PaintBuffer<'static> {
+        let light = Light::new(Point3D::new(0.0, 0.0, -1.0), (1.0, 1.0, 1.0));
+        let mut buffer = PaintBuffer::new(64, 64);
+        buffer.clear(settings.background);
+
+        Object3D::new(triangles).paint_to_buffer(&mut buffer, Scene::new(test_camera(), light), settings, None);
+
+        buffer
+    }
+
+    #[test]
+    fn shaded_wireframe_hides_occluded_edges() {
+        // the far wall is entirely behind the near one
+        let triangles = [wall(3.0, 0.5, 0xFFFFFF), wall(6.0, 0.5, 0xFFFFFF)].concat();
+        let settings = RenderSettings { render_mode: RenderMode::ShadedWireframe, ..RenderSettings::default() };
+        let buffer = render_triangles(triangles, settings);
+
+        // the top edges of the near and far walls
+        assert_eq!(pixel(&buffer, 32, 21), settings.wireframe_color);
+        assert_ne!(pixel(&buffer, 32, 27), settings.wireframe_color);
+
+        // without the near wall in the way, the far one's edge shows
+        let buffer = render_triangles(wall(6.0, 0.5, 0xFFFFFF).to_vec(), settings);
+        assert_eq!(pixel(&buffer, 32, 27), settings.wireframe_color);
+    }
+
+    #[test]
+    fn fragments_beyond_far_plane_are_dropped() {
+        let camera = Camera::perspective(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0), Camera::DEFAULT_FOV_Y, 1.0, 0.1, 4.0);
+        let scene = Scene::new(camera, Light::new(Point3D::new(0.0, 0.0, -1.0), (1.0, 1.0, 1.0)));
+
+        let mut buffer = PaintBuffer::new(64, 64);
+        paint_all(&wall(5.0, 1.0, 0xFFFFFF), &mut buffer, scene, RenderSettings::default());
+        assert!(buffer.z_buffer.iter().all(|&z| z == f64::MAX));
+
+        paint_all(&wall(3.0, 1.0, 0xFFFFFF), &mut buffer, scene, RenderSettings::default());
+        assert!((buffer.z_buffer[32 + 32 * 64] - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn specular_models_differ_and_stay_finite() {
+        let normal = Point3D::new(0.0, 1.0, 0.0);
+        let light_dir = Point3D::new(1.0, 1.0, 0.0).normalized();
+        let view_dir = Point3D::new(0.0, 1.0, 0.0);
+
+        let blinn_phong = SpecularModel::BlinnPhong.brightness(normal, light_dir, view_dir, 4.0);
+        let phong = SpecularModel::Phong.brightness(normal, light_dir, view_dir, 4.0);
+
+        assert!(blinn_phong.is_finite() && phong.is_finite());
+        assert!((blinn_phong - phong).abs() > 0.1);
+    }
+
+    #[test]
+    fn highlight_peaks_where_half_vector_meets_normal() {
+        // camera and light both 4 above a finely split floor, 2 apart. the
+        // half vector lines up with the floor's normal halfway between them,
+        // at (1, 0, 0), which is column 48 of the middle row
+        let camera = Camera::new(Point3D::new(0.0, 4.0, 0.0), Point3D::new(0.0, -1.0, 0.0));
+        let light = Light::new(Point3D::new(2.0, 4.0, 0.0), (1.0, 1.0, 1.0));
+
+        let mut floor = Object3D::ground_plane(8.0, 16, 0xFFFFFF, 0xFFFFFF);
+        floor.set_material(Material { diffuse: 0.0, ..Material::default() });
+
+        let settings = RenderSettings { ambient: 0.0, ..RenderSettings::default() };
+        let mut buffer = PaintBuffer::new(64, 64);
+        floor.paint_to_buffer(&mut buffer, Scene::new(camera, light), settings, None);
+
+        let brightest = buffer.pixel_buffer.iter().map(|pixel| pixel & 0xFF).max().unwrap();
+        assert_eq!(pixel(&buffer, 48, 32) & 0xFF, brightest);
+        assert!(pixel(&buffer, 32, 32) & 0xFF < brightest);
+    }
+
+    #[test]
+    fn fxaa_softens_edges_only() {
+        let mut buffer = PaintBuffer::new(8, 8);
+        for y in 0..8 {
+            for x in 4..8 {
+                buffer.pixel_buffer[(x + y * 8) as usize] = 0xFFFFFF;
+            }
+        }
+
+        buffer.apply_fxaa();
+
+        for x in [3, 4] {
+            let gray = pixel(&buffer, x, 4);
+            assert!(gray != 0x000000 && gray != 0xFFFFFF);
+            assert_eq!(gray >> 16, gray & 0xFF);
+        }
+
+        assert_eq!(pixel(&buffer, 0, 4), 0x000000);
+        assert_eq!(pixel(&buffer, 7, 4), 0xFFFFFF);
+    }
+
+    // paints a triangle in a flat color, without any lighting
+    fn paint_flat(tri: Triangle3D, buffer: &mut PaintBuffer, scene: Scene, settings: RenderSettings, color: u32) {
+        tri.paint_to_buffer(buffer, scene, settings, |_, _, _| (color, unpack_color(color)));
+    }
+
+    fn test_scene() -> Scene {
+        Scene::new(test_camera(), Light::new(Point3D::new(0.0, 0.0, -1.0), (1.0, 1.0, 1.0)))
+    }
+
+    #[test]
+    fn depth_bias_lets_later_coplanar_triangles_win() {
+        let [tri, _] = wall(5.0, 1.0, 0xFFFFFF);
+
+        let paint_twice = |depth_bias: f64| {
+            let settings = RenderSettings { depth_bias, ..RenderSettings::default() };
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_flat(tri.tri, &mut buffer, test_scene(), settings, 0xFF0000);
+            paint_flat(tri.tri, &mut buffer, test_scene(), settings, 0x0000FF);
+
+            pixel(&buffer, 28, 28)
+        };
+
+        assert_eq!(paint_twice(0.0), 0xFF0000);
+        assert_eq!(paint_twice(1e-6), 0x0000FF);
+    }
+
+    // a seeded generator, so failures can be reproduced
+    fn test_rng() -> rand::rngs::StdRng {
+        rand::SeedableRng::seed_from_u64(0x5EED)
+    }
+
+    fn random_point(rng: &mut rand::rngs::StdRng) -> Point2D {
+        use rand::Rng;
+
+        Point2D::new(rng.gen_range(-0.25..1.25), rng.gen_range(-0.25..1.25))
+    }
+
+    #[test]
+    fn edge_function_matches_inline_formula() {
+        let mut rng = test_rng();
+
+        for _ in 0..1000 {
+            let [a, b, c] = [(); 3].map(|_| random_point(&mut rng));
+            let inline = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+
+            assert_eq!(Triangle2D::edge_function(a, b, c), inline);
+        }
+    }
+
+    #[test]
+    fn segment_intersect_cases() {
+        let p = Point2D::new;
+
+        let crossing = segment_intersect(p(0.0, 0.0), p(2.0, 2.0), p(0.0, 2.0), p(2.0, 0.0)).unwrap();
+        assert!(crossing.approx_eq(p(1.0, 1.0), 1e-12));
+
+        // parallel, and collinear with an overlap
+        assert_eq!(segment_intersect(p(0.0, 0.0), p(2.0, 0.0), p(0.0, 1.0), p(2.0, 1.0)), None);
+        assert_eq!(segment_intersect(p(0.0, 0.0), p(2.0, 0.0), p(1.0, 0.0), p(3.0, 0.0)), None);
+
+        // would cross if they were longer
+        assert_eq!(segment_intersect(p(0.0, 0.0), p(0.4, 0.4), p(0.0, 2.0), p(2.0, 0.0)), None);
+    }
+
+    #[test]
+    fn clip_to_rect_cuts_off_vertex_past_right_edge() {
+        let triangle = [Point2D::new(0.2, 0.2), Point2D::new(1.4, 0.5), Point2D::new(0.2, 0.8)];
+        let clipped = clip_to_rect(&triangle, Rect::unit());
+
+        // the vertex past the edge is replaced by the two points where the
+        // triangle's edges cross it
+        assert_eq!(clipped.len(), 4);
+        assert!(clipped.iter().all(|p| p.x <= 1.0 && p.x >= 0.0));
+        assert!(clipped.iter().any(|p| p.approx_eq(Point2D::new(1.0, 0.4), 1e-12)));
+        assert!(clipped.iter().any(|p| p.approx_eq(Point2D::new(1.0, 0.6), 1e-12)));
+        assert!(clipped.contains(&triangle[0]) && clipped.contains(&triangle[2]));
+    }
+
+    #[test]
+    fn front_face_decides_what_gets_culled() {
+        let [tri, _] = wall(5.0, 1.0, 0xFFFFFF);
+
+        let painted = |front_face: FrontFace| {
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_flat(tri.tri, &mut buffer, test_scene(), RenderSettings { front_face, ..RenderSettings::default() }, 0xFFFFFF);
+            buffer.pixel_buffer.iter().filter(|&&pixel| pixel != 0).count()
+        };
+
+        assert!(painted(FrontFace::Cw) > 0);
+        assert_eq!(painted(FrontFace::Ccw), 0);
+    }
+
+    // two triangles folded along the shared edge from the origin to +y
+    fn roof(normal: Point3D) -> Object3D {
+        let normal_tri = Triangle3D::new(normal, normal, normal);
+        let origin = Point3D::new(0.0, 0.0, 0.0);
+        let top = Point3D::new(0.0, 1.0, 0.0);
+
+        Object3D::new(vec![
+            ColorTriangle::new(0xFFFFFF, Triangle3D::new(origin, top, Point3D::new(-1.0, 0.0, -1.0)), normal_tri),
+            ColorTriangle::new(0xFFFFFF, Triangle3D::new(origin, Point3D::new(1.0, 0.0, -1.0), top), normal_tri),
+        ])
+    }
+
+    #[test]
+    fn flat_normals_are_per_face() {
+        let mut object = roof(Point3D::new(0.0, 0.0, 0.0));
+        object.recompute_normals(NormalMode::Flat);
+
+        for tri in &object.triangles {
+            let face_normal = tri.tri.face_normal();
+            assert!(tri.normal_tri.approx_eq(Triangle3D::new(face_normal, face_normal, face_normal), 1e-12));
+        }
+        assert!(!object.triangles[0].normal_tri.a.approx_eq(object.triangles[1].normal_tri.a, 1e-3));
+    }
+
+    #[test]
+    fn smooth_normals_average_shared_vertices() {
+        let mut object = roof(Point3D::new(0.0, 0.0, 0.0));
+        object.recompute_normals(NormalMode::Smooth);
+
+        let shared = object.triangles[0].tri.face_normal().translated_by(object.triangles[1].tri.face_normal()).normalized();
+        assert!(object.triangles[0].normal_tri.a.approx_eq(shared, 1e-12));
+        assert!(object.triangles[1].normal_tri.a.approx_eq(shared, 1e-12));
+
+        // the corners only one face touches keep that face's normal
+        assert!(object.triangles[0].normal_tri.c.approx_eq(object.triangles[0].tri.face_normal(), 1e-12));
+    }
+
+    #[test]
+    fn fill_missing_normals_keeps_given_ones() {
+        let given = Point3D::new(0.0, 0.6, 0.8);
+        let mut object = roof(given);
+        object.triangles[1].normal_tri.b = Point3D::new(0.0, 0.0, 0.0);
+        object.fill_missing_normals();
+
+        assert!(object.triangles[0].normal_tri.approx_eq(Triangle3D::new(given, given, given), 1e-12));
+        assert!(object.triangles[1].normal_tri.b.approx_eq(object.triangles[1].tri.face_normal(), 1e-12));
+        assert!(object.triangles[1].normal_tri.a.approx_eq(given, 1e-12));
+    }
+
+    #[test]
+    fn view_axis_projects_to_screen_center() {
+        let (pixel, depth) = Point3D::new(0.0, 0.0, 5.0).project_to_screen(test_camera(), 64, 48);
+
+        assert_eq!(pixel, Some((32, 24)));
+        assert_eq!(depth, 5.0);
+        assert_eq!(Point3D::new(0.0, 0.0, -1.0).project_to_screen(test_camera(), 64, 48).0, None);
+    }
+
+    #[test]
+    fn triangle_id_colors_are_stable_and_distinct() {
+        let colors = (0..256).map(triangle_id_color).collect::<Vec<u32>>();
+
+        assert_eq!(colors, (0..256).map(triangle_id_color).collect::<Vec<u32>>());
+        assert!(colors.iter().all(|&color| color <= 0xFFFFFF));
+
+        let distinct = colors.iter().collect::<std::collections::HashSet<&u32>>();
+        assert_eq!(distinct.len(), colors.len());
+    }
+
+    #[test]
+    fn overdraw_counts_every_overlapping_fragment() {
+        let mut buffer = PaintBuffer::new(64, 64);
+        buffer.enable_overdraw();
+
+        for z in [3.0, 4.0, 5.0] {
+            paint_all(&wall(z, z * 0.25, 0xFFFFFF), &mut buffer, test_scene(), RenderSettings::default());
+        }
+
+        // inside one triangle of each wall, away from their shared diagonal
+        let overdraw = buffer.overdraw.as_ref().unwrap();
+        assert_eq!(overdraw[(24 + 24 * 64) as usize], 3);
+        assert_eq!(overdraw[(2 + 2 * 64) as usize], 0);
+
+        buffer.visualize_overdraw();
+        assert_eq!(pixel(&buffer, 24, 24), 0x00FF00);
+        assert_eq!(pixel(&buffer, 2, 2), 0x000000);
+    }
+
+    // a triangle somewhere in front of test_camera, partly off screen at times
+    fn random_triangle(rng: &mut rand::rngs::StdRng) -> Triangle3D {
+        use rand::Rng;
+
+        let mut point = || Point3D::new(rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0), rng.gen_range(2.0..6.0));
+        Triangle3D::new(point(), point(), point())
+    }
+
+    // paints each triangle into its own buffer with the given rasterizer
+    fn rasterize_each(triangles: &[Triangle3D], rasterizer: Rasterizer) -> Vec<Vec<u32>> {
+        let settings = RenderSettings { rasterizer, cull_back_faces: false, ..RenderSettings::default() };
+
+        triangles
+            .iter()
+            .map(|&tri| {
+                let mut buffer = PaintBuffer::new(48, 32);
+                paint_flat(tri, &mut buffer, test_scene(), settings, 0xFFFFFF);
+                buffer.pixel_buffer.to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scanline_matches_bounding_box() {
+        let mut rng = test_rng();
+        let triangles = (0..500).map(|_| random_triangle(&mut rng)).collect::<Vec<Triangle3D>>();
+
+        assert_eq!(rasterize_each(&triangles, Rasterizer::Scanline), rasterize_each(&triangles, Rasterizer::BoundingBox));
+    }
+
+    #[test]
+    fn weight_steps_match_get_weights_at() {
+        let mut rng = test_rng();
+        let dx = 1.0 / 64.0;
+
+        for _ in 0..200 {
+            let tri = Triangle2D::new(random_point(&mut rng), random_point(&mut rng), random_point(&mut rng));
+            let start = random_point(&mut rng);
+            let Some(mut weights) = tri.get_weights_at(start) else {
+                continue;
+            };
+            let step = tri.weight_steps_x(dx);
+
+            for i in 1..64 {
+                weights = (weights.0 + step.0, weights.1 + step.1, weights.2 + step.2);
+                let expected = tri.get_weights_at(Point2D::new(start.x + i as f64 * dx, start.y)).unwrap();
+
+                let scale = 1.0 + expected.0.abs() + expected.1.abs() + expected.2.abs();
+                assert!((weights.0 - expected.0).abs() < 1e-9 * scale);
+                assert!((weights.1 - expected.1).abs() < 1e-9 * scale);
+                assert!((weights.2 - expected.2).abs() < 1e-9 * scale);
+            }
+        }
+    }
+
+    // the weights of random triangles' rows, as the rasterizer steps along
+    // them. see rasterizers_match_the_reference for the painted pixels,
+    // which checks weights_x4 with and without the simd feature
+    #[cfg(feature = "simd")]
+    #[test]
+    fn weights_x4_matches_scalar() {
+        use rand::Rng;
+        let mut rng = test_rng();
+        let (width, height) = (48, 32);
+
+        for _ in 0..200 {
+            let tri = Triangle2D::new(random_point(&mut rng), random_point(&mut rng), random_point(&mut rng));
+            if tri.is_degenerate(Triangle2D::DEGENERATE_AREA) {
+                continue;
+            }
+
+            let step = tri.weight_steps_x(1.0 / width as f64);
+            let y = rng.gen_range(0..height);
+            let row = tri.get_weights_at(Point2D::new(0.0, y as f64 / height as f64)).unwrap();
+
+            for x in (0..width).step_by(4) {
+                assert_eq!(weights_x4(row, step, x), weights_x4_scalar(row, step, x));
+            }
+        }
+    }
+
+    #[test]
+    fn hdr_buffer_keeps_values_above_one() {
+        let scene = Scene::new(test_camera(), Light::new(Point3D::new(0.0, 0.0, -1.0), (3.0, 3.0, 3.0)));
+        let mut buffer = PaintBuffer::new(64, 64);
+        buffer.enable_hdr();
+
+        paint_all(&wall(5.0, 1.0, 0xFFFFFF), &mut buffer, scene, RenderSettings::default());
+
+        let (r, g, b) = buffer.hdr_buffer.as_ref().unwrap()[(32 + 32 * 64) as usize];
+        assert!(r > 1.0 && g > 1.0 && b > 1.0, "hdr pixel is ({}, {}, {})", r, g, b);
+        assert_eq!(pixel(&buffer, 32, 32), 0xFFFFFF);
+    }
+
+    #[test]
+    fn tone_mapping_brings_bright_values_below_one() {
+        assert_eq!(ToneMap::Reinhard.apply(4.0), 0.8);
+        assert!(ToneMap::Aces.apply(4.0) < 1.0);
+
+        let mut buffer = PaintBuffer::new(1, 1);
+        buffer.enable_hdr();
+        buffer.hdr_buffer.as_mut().unwrap()[0] = (4.0, 4.0, 4.0);
+        buffer.tone_map(ToneMap::Reinhard);
+
+        assert_eq!(pixel(&buffer, 0, 0), 0xCCCCCC);
+    }
+
+    #[test]
+    fn exposure_scales_brightness_before_clamping() {
+        let mut triangles = wall(5.0, 1.0, 0xFFFFFF);
+        for tri in &mut triangles {
+            tri.material = Material { diffuse: 0.25, specular: 0.0, ..Material::default() };
+        }
+
+        let render = |exposure: f64| {
+            let settings = RenderSettings { ambient: 0.0, exposure, ..RenderSettings::default() };
+            let mut buffer = PaintBuffer::new(64, 64);
+            buffer.enable_hdr();
+            paint_all(&triangles, &mut buffer, test_scene(), settings);
+
+            (pixel(&buffer, 32, 32) & 0xFF, buffer.hdr_buffer.as_ref().unwrap()[(32 + 32 * 64) as usize].0)
+        };
+
+        let (dim, dim_linear) = render(1.0);
+        let (bright, bright_linear) = render(2.0);
+        assert!(dim > 0);
+        assert!(bright.abs_diff(2 * dim) <= 1, "{} isn't twice {}", bright, dim);
+        assert!((bright_linear - 2.0 * dim_linear).abs() < 1e-12);
+
+        // far past 1.0 the packed pixel clamps while the linear value doesn't
+        let (clamped, clamped_linear) = render(8.0);
+        assert_eq!(clamped, 0xFF);
+        assert!(clamped_linear > 1.0);
+    }
+
+    #[test]
+    fn light_orbit_positions() {
+        let orbit = LightOrbit::new(Point3D::new(1.0, 2.0, 3.0), 2.0, 8.0);
+
+        assert!(orbit.position_at(0.0).approx_eq(Point3D::new(3.0, 2.0, 3.0), 1e-12));
+        assert!(orbit.position_at(2.0).approx_eq(Point3D::new(1.0, 2.0, 5.0), 1e-12));
+        assert!(orbit.position_at(8.0).approx_eq(orbit.position_at(0.0), 1e-12));
+
+        let stuck = LightOrbit::new(Point3D::new(1.0, 2.0, 3.0), 2.0, 0.0);
+        assert!(stuck.position_at(5.0).approx_eq(Point3D::new(3.0, 2.0, 3.0), 1e-12));
+    }
+
+    #[test]
+    fn matrix_projection_matches_manual_projection() {
+        let mut camera = Camera::new(Point3D::new(1.0, 2.0, -3.0), Point3D::new(0.0, 0.0, 1.0));
+        camera.aspect = 1.5;
+        let (scale_x, scale_y) = camera.projection_scale();
+
+        // translate, scale by the field of view, flip y and divide by depth
+        let manual = |p: Point3D| {
+            let depth = p.z - camera.position.z;
+            Point2D::new(
+                0.5 + scale_x * (p.x - camera.position.x) / depth,
+                0.5 - scale_y * (p.y - camera.position.y) / depth,
+            )
+        };
+
+        let mut rng = test_rng();
+        for _ in 0..100 {
+            let tri = random_triangle(&mut rng);
+            let expected = Triangle2D::new(manual(tri.a), manual(tri.b), manual(tri.c));
+
+
+            let projected = tri.to_screen(camera);
+            for (p, expected) in [(projected.a, expected.a), (projected.b, expected.b), (projected.c, expected.c)] {
+                assert!(p.approx_eq(expected, 1e-12), "{:?} isn't {:?}", p, expected);
+            }
+
+            let offset = Point3D::new(0.5, -1.0, 2.0);
+            assert!(tri.transformed(&Matrix4x4::translation(offset)).approx_eq(tri.translated_by(offset), 1e-12));
+        }
+    }
+
+    #[test]
+    fn scene_screen_transform_matches_to_screen() {
+        let camera = Camera::new(Point3D::new(0.5, -1.0, -2.0), Point3D::new(0.2, 0.1, 1.0));
+        let Scene(_, _, screen_transform) = Scene::new(camera, Light::new(Point3D::new(0.0, 0.0, 0.0), (1.0, 1.0, 1.0)));
+
+        let mut rng = test_rng();
+        for _ in 0..100 {
+            let tri = random_triangle(&mut rng);
+            let (batched, single) = (tri.project_with(&screen_transform), tri.to_screen(camera));
+
+            assert_eq!([batched.a, batched.b, batched.c], [single.a, single.b, single.c]);
+        }
+    }
+
+    #[test]
+    fn points_above_the_axis_land_in_the_upper_half() {
+        let top = Camera::viewport_matrix().transform_point(Point3D::new(0.0, 1.0, 0.0));
+        assert_eq!((top.x, top.y), (0.5, 0.0));
+
+        let above = test_camera().screen_transform().transform_point(Point3D::new(0.0, 1.0, 5.0));
+        let below = test_camera().screen_transform().transform_point(Point3D::new(0.0, -1.0, 5.0));
+        assert!(above.y < 0.5 && below.y > 0.5);
+        assert!((above.x - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn set_view_dir_normalizes() {
+        let mut camera = test_camera();
+        camera.set_view_dir(Point3D::new(3.0, 0.0, 4.0));
+        assert!(camera.view_dir.approx_eq(Point3D::new(0.6, 0.0, 0.8), 1e-12));
+
+        camera.set_view_dir(Point3D::new(0.0, 0.0, 0.0));
+        assert!(camera.view_dir.approx_eq(Point3D::new(0.6, 0.0, 0.8), 1e-12));
+
+        let camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, -5.0, 0.0));
+        assert!(camera.view_dir.approx_eq(Point3D::new(0.0, -1.0, 0.0), 1e-12));
+    }
+
+    #[test]
+    fn ssao_darkens_crevices_only() {
+        let flat_buffer = || {
+            let mut buffer = PaintBuffer::new(9, 9);
+            buffer.clear(0xFFFFFF);
+            buffer.z_buffer.fill(10.0);
+            buffer
+        };
+
+        let mut flat = flat_buffer();
+        flat.apply_ssao(2, 0.5);
+        assert!(flat.pixel_buffer.iter().all(|&pixel| pixel == 0xFFFFFF));
+
+        // a pit in the middle, everything around it is closer
+        let mut pit = flat_buffer();
+        pit.z_buffer[4 + 4 * 9] = 11.0;
+        pit.apply_ssao(2, 0.5);
+        assert_eq!(pixel(&pit, 4, 4), 0x7F7F7F);
+        assert_eq!(pixel(&pit, 1, 1), 0xFFFFFF);
+    }
+
+    #[test]
+    fn zero_diffuse_leaves_ambient_and_specular() {
+        let render = |material: Material| {
+            let mut triangles = wall(5.0, 1.0, 0xFFFFFF);
+            for tri in &mut triangles {
+                tri.material = material;
+            }
+
+            let settings = RenderSettings { ambient: 0.2, ..RenderSettings::default() };
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_all(&triangles, &mut buffer, test_scene(), settings);
+
+            pixel(&buffer, 32, 32) & 0xFF
+        };
+
+        assert_eq!(render(Material { diffuse: 0.0, specular: 0.0, ..Material::default() }), 0x33);
+        assert!(render(Material { diffuse: 0.0, ..Material::default() }) > 0x33);
+    }
+
+    #[test]
+    fn emissive_shows_without_light() {
+        // the light is behind the wall, so only emission is left
+        let scene = Scene::new(test_camera(), Light::new(Point3D::new(0.0, 0.0, 10.0), (1.0, 1.0, 1.0)));
+        let settings = RenderSettings { ambient: 0.0, ..RenderSettings::default() };
+
+        let render = |emissive: u32| {
+            let mut triangles = wall(5.0, 1.0, 0xFFFFFF);
+            for tri in &mut triangles {
+                tri.material.emissive = emissive;
+            }
+
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_all(&triangles, &mut buffer, scene, settings);
+            pixel(&buffer, 32, 32)
+        };
+
+        assert_eq!(render(0x000000), 0x000000);
+
+        let glow = render(0x204080);
+        for shift in [16, 8, 0] {
+            assert!(((glow >> shift) & 0xFF).abs_diff((0x204080 >> shift) & 0xFF) <= 1, "{:06X} isn't the emissive color", glow);
+        }
+    }
+
+    // the pixels (as buffer indices) of a width x height buffer a triangle
+    // covers, found by testing every pixel of the settings' bounds on its
+    // own. far too slow for real use, but simple enough to check the
+    // rasterizers against
+    fn reference_rasterize(tri: Triangle3D, width: u32, height: u32, scene: Scene, settings: RenderSettings) -> Vec<usize> {
+        let Scene(camera, _, screen_transform) = scene;
+        let projected = tri.to_viewport(&screen_transform, settings.viewport);
+        let (range_x, range_y) = settings.pixel_bounds(width, height);
+        let mut covered = Vec::new();
+
+        for y in range_y {
+            for x in range_x.clone() {
+                let p = Point2D::new(x as f64 / width as f64, y as f64 / height as f64);
+                let Some((weight_a, weight_b, weight_c)) = projected.get_weights_at(p) else {
+                    return Vec::new();
+                };
+
+                let z_val = camera.depth_of(tri.a) * weight_a + camera.depth_of(tri.b) * weight_b + camera.depth_of(tri.c) * weight_c;
+                if weight_a >= 0.0 && weight_b >= 0.0 && weight_c >= 0.0 && z_val >= camera.near && z_val <= camera.far {
+                    covered.push((x + y * width) as usize);
+                }
+            }
+        }
+
+        covered
+    }
+
+    // also covers the inside mask of weights_x4, with or without simd
+    #[test]
+    fn rasterizers_match_the_reference() {
+        let mut rng = test_rng();
+
+        for _ in 0..500 {
+            let tri = random_triangle(&mut rng);
+            let expected = reference_rasterize(tri, 48, 32, test_scene(), RenderSettings { cull_back_faces: false, ..RenderSettings::default() });
+
+            for rasterizer in [Rasterizer::BoundingBox, Rasterizer::Scanline] {
+                let settings = RenderSettings { rasterizer, cull_back_faces: false, ..RenderSettings::default() };
+                let mut covered = Vec::new();
+                tri.rasterize(48, 32, test_scene(), settings, |index, _, _| covered.push(index));
+
+                assert_eq!(covered, expected, "{:?} differs from the reference for {:?}", rasterizer, tri);
+            }
+        }
+    }
+
+    #[test]
+    fn sub_undoes_translated_by() {
+        let mut rng = test_rng();
+
+        for _ in 0..100 {
+            let (p, offset) = (random_point(&mut rng), random_point(&mut rng));
+            assert!(p.translated_by(offset).sub(offset).approx_eq(p, 1e-12));
+
+            let ndc = Camera::viewport_matrix().transform_point(Point3D::new(p.x, p.y, 0.0));
+            assert!(Camera::screen_to_ndc(Point2D::new(ndc.x, ndc.y)).approx_eq(p, 1e-12));
+        }
+    }
+
+    #[test]
+    fn moving_the_viewport_center_moves_the_image() {
+        let render = |viewport: Viewport| {
+            let settings = RenderSettings { viewport, ..RenderSettings::default() };
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_all(&wall(5.0, 1.0, 0xFFFFFF), &mut buffer, test_scene(), settings);
+            buffer
+        };
+
+        let centered = render(Viewport::full());
+        let moved = render(Viewport::full().with_center(Point2D::new(0.75, 0.5)));
+
+        // a quarter of the width to the right, nothing else changes
+        for y in 0..64 {
+            for x in 0..48 {
+                assert_eq!(pixel(&moved, x + 16, y), pixel(&centered, x, y), "pixel ({}, {})", x, y);
+            }
+        }
+        assert_ne!(pixel(&centered, 32, 32), 0);
+        assert_eq!(pixel(&moved, 32, 32), 0);
+    }
+
+    #[test]
+    fn two_sided_lighting_lights_back_faces() {
+        // the wall turned around: wound the other way with its normals
+        // pointing away from the camera and the light
+        let normal = Point3D::new(0.0, 0.0, 1.0);
+        let back = wall(5.0, 1.0, 0xFFFFFF).map(|tri| {
+            ColorTriangle::new(tri.color, Triangle3D::new(tri.tri.a, tri.tri.c, tri.tri.b), Triangle3D::new(normal, normal, normal))
+        });
+
+        let render = |two_sided_lighting: bool| {
+            let settings = RenderSettings { cull_back_faces: false, two_sided_lighting, ambient: 0.0, ..RenderSettings::default() };
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_all(&back, &mut buffer, test_scene(), settings);
+
+            (pixel(&buffer, 32, 32), buffer.z_buffer[32 + 32 * 64])
+        };
+
+        let (one_sided, one_sided_z) = render(false);
+        assert_eq!(one_sided, 0x000000);
+        assert!(one_sided_z < f64::MAX);
+
+        let (two_sided, _) = render(true);
+        assert!(two_sided & 0xFF > 0xC0, "back face is only {:06X}", two_sided);
+    }
+
+    #[test]
+    fn nearer_points_hide_farther_ones() {
+        let near = Point3D::new(0.0, 0.0, 5.0);
+        let far = Point3D::new(0.0, 0.0, 8.0);
+
+        for order in [[(near, 0x00FF00), (far, 0xFF0000)], [(far, 0xFF0000), (near, 0x00FF00)]] {
+            let mut buffer = PaintBuffer::new(64, 64);
+            for (point, color) in order {
+                render_points(&mut buffer, test_scene(), RenderSettings::default(), &[point], color, 3);
+            }
+
+            assert_eq!(pixel(&buffer, 32, 32), 0x00FF00);
+            assert_eq!(buffer.z_buffer[32 + 32 * 64], 5.0);
+        }
+    }
+
+    #[test]
+    fn shallow_aa_line_splits_between_rows() {
+        // a slope of 1/4, a quarter of the way into row 13 at column 16
+        let mut buffer = PaintBuffer::new(32, 32);
+        buffer.draw_line_aa(Point2D::new(4.0 / 32.0, 10.25 / 32.0), Point2D::new(28.0 / 32.0, 16.25 / 32.0), 0xFFFFFF);
+
+        let upper = pixel(&buffer, 16, 13) & 0xFF;
+        let lower = pixel(&buffer, 16, 14) & 0xFF;
+        assert!(upper.abs_diff(191) <= 1 && lower.abs_diff(63) <= 1, "rows got {} and {}", upper, lower);
+
+        assert_eq!(pixel(&buffer, 16, 12), 0);
+        assert_eq!(pixel(&buffer, 16, 15), 0);
+    }
+
+    #[test]
+    fn coverage_of_the_upper_left_quadrant() {
+        // the right angle sits between pixel centers 15 and 16, and the other
+        // two corners are far enough out that the buffer edges cut them off
+        let corner = 15.5 / 32.0;
+        let tri = Triangle2D::new(Point2D::new(corner, corner), Point2D::new(corner, -10.0), Point2D::new(-10.0, corner));
+
+        let expected = (0..16).flat_map(|y| (0..16).map(move |x| (x, y))).collect::<Vec<(u32, u32)>>();
+        assert_eq!(tri.coverage(32, 32), expected);
+    }
+
+    #[test]
+    fn greater_depth_func_keeps_farther_fragments() {
+        let [near, _] = wall(3.0, 1.0, 0xFFFFFF);
+        let [far, _] = wall(6.0, 2.0, 0xFFFFFF);
+        let mut buffer = PaintBuffer::new(64, 64);
+
+        paint_flat(near.tri, &mut buffer, test_scene(), RenderSettings::default(), 0x00FF00);
+        let greater = RenderSettings { depth_func: DepthFunc::Greater, ..RenderSettings::default() };
+        paint_flat(far.tri, &mut buffer, test_scene(), greater, 0xFF0000);
+
+        assert_eq!(pixel(&buffer, 28, 28), 0xFF0000);
+        assert!((buffer.z_buffer[28 + 28 * 64] - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn without_depth_writes_the_last_triangle_drawn_wins() {
+        let [near, _] = wall(3.0, 1.0, 0xFFFFFF);
+        let [far, _] = wall(6.0, 2.0, 0xFFFFFF);
+        let settings = RenderSettings { depth_write: false, ..RenderSettings::default() };
+
+        for (first, second) in [((near, 0x00FF00), (far, 0xFF0000)), ((far, 0xFF0000), (near, 0x00FF00))] {
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_flat(first.0.tri, &mut buffer, test_scene(), settings, first.1);
+            paint_flat(second.0.tri, &mut buffer, test_scene(), settings, second.1);
+
+            assert_eq!(pixel(&buffer, 28, 28), second.1);
+            assert_eq!(buffer.z_buffer[28 + 28 * 64], f64::MAX);
+        }
+    }
+
+    #[test]
+    fn scissor_limits_drawing() {
+        let settings = RenderSettings { scissor: Some((16, 20, 8, 4)), ..RenderSettings::default() };
+        let mut buffer = PaintBuffer::new(64, 64);
+
+        // covers the whole buffer
+        paint_all(&wall(5.0, 5.0, 0xFFFFFF), &mut buffer, test_scene(), settings);
+
+        for y in 0..64 {
+            for x in 0..64 {
+                let inside = (16..24).contains(&x) && (20..24).contains(&y);
+                assert_eq!(pixel(&buffer, x, y) != 0, inside, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn composite_over_keeps_the_nearer_pixel() {
+        let mut base = PaintBuffer::new(3, 1);
+        base.pixel_buffer.copy_from_slice(&[0x111111, 0x222222, 0x333333]);
+        base.z_buffer.copy_from_slice(&[5.0, 5.0, 5.0]);
+
+        // nearer, farther and never drawn to
+        let mut layer = PaintBuffer::new(3, 1);
+        layer.pixel_buffer.copy_from_slice(&[0xAAAAAA, 0xBBBBBB, 0xCCCCCC]);
+        layer.z_buffer.copy_from_slice(&[2.0, 8.0, f64::MAX]);
+
+        base.composite_over(&layer);
+
+        assert_eq!(base.pixel_buffer.to_vec(), vec![0xAAAAAA, 0x222222, 0x333333]);
+        assert_eq!(base.z_buffer, vec![2.0, 5.0, 5.0]);
+    }
+
+    #[test]
+    fn collinear_triangles_paint_nothing() {
+        let flat = Triangle2D::new(Point2D::new(0.1, 0.1), Point2D::new(0.5, 0.5), Point2D::new(0.9, 0.9));
+        assert!(flat.is_degenerate(Triangle2D::DEGENERATE_AREA));
+        assert!(!flat.contains_point(Point2D::new(0.5, 0.5)));
+
+        // seen edge-on, all three corners project onto the same line
+        let edge_on = Triangle3D::new(Point3D::new(0.0, -1.0, 4.0), Point3D::new(0.0, 1.0, 5.0), Point3D::new(0.0, 0.0, 6.0));
+        let mut buffer = PaintBuffer::new(64, 64);
+        paint_flat(edge_on, &mut buffer, test_scene(), RenderSettings { cull_back_faces: false, ..RenderSettings::default() }, 0xFFFFFF);
+
+        assert!(buffer.pixel_buffer.iter().all(|&pixel| pixel == 0));
+        assert!(buffer.z_buffer.iter().all(|&z| z == f64::MAX));
+    }
+
+    #[test]
+    fn degenerate_triangles_have_no_weights() {
+        let point = Triangle2D::new(Point2D::new(0.5, 0.5), Point2D::new(0.5, 0.5), Point2D::new(0.5, 0.5));
+        let line = Triangle2D::new(Point2D::new(0.0, 0.0), Point2D::new(0.5, 0.25), Point2D::new(1.0, 0.5));
+
+        assert_eq!(point.get_weights_at(Point2D::new(0.5, 0.5)), None);
+        assert_eq!(line.get_weights_at(Point2D::new(0.5, 0.25)), None);
+
+        let tri = Triangle2D::new(Point2D::new(0.0, 0.0), Point2D::new(1.0, 0.0), Point2D::new(0.0, 1.0));
+        assert_eq!(tri.get_weights_at(Point2D::new(0.0, 0.0)), Some((1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn specular_color_is_separate_from_the_light_color() {
+        let mut light = Light::new(Point3D::new(0.0, 0.0, -1.0), (1.0, 0.0, 0.0));
+        light.specular_color = (1.0, 1.0, 1.0);
+        let scene = Scene::new(test_camera(), light);
+
+        let render = |material: Material| {
+            let mut triangles = wall(5.0, 1.0, 0xFFFFFF);
+            for tri in &mut triangles {
+                tri.material = material;
+            }
+
+            let mut buffer = PaintBuffer::new(64, 64);
+            paint_all(&triangles, &mut buffer, scene, RenderSettings { ambient: 0.0, ..RenderSettings::default() });
+            unpack_color(pixel(&buffer, 32, 32))
+        };
+
+        let (r, g, b) = render(Material { specular: 0.0, ..Material::default() });
+        assert!(r > 0.5 && g == 0.0 && b == 0.0, "diffuse is ({}, {}, {})", r, g, b);
+
+        let (r, g, b) = render(Material { diffuse: 0.0, ..Material::default() });
+        assert!(r > 0.5 && (r - g).abs() < 0.01 && (r - b).abs() < 0.01, "highlight is ({}, {}, {})", r, g, b);
+    }
+
+    #[test]
+    fn point_min_max_clamp() {
+        let a = Point3D::new(-1.0, 2.0, -3.0);
+        let b = Point3D::new(4.0, -5.0, 0.0);
+
+        assert_eq!(Point3D::min(a, b), Point3D::new(-1.0, -5.0, -3.0));
+        assert_eq!(Point3D::max(a, b), Point3D::new(4.0, 2.0, 0.0));
+
+        let (low, high) = (Point3D::new(-2.0, -2.0, -2.0), Point3D::new(2.0, 2.0, 2.0));
+        assert_eq!(b.clamp(low, high), Point3D::new(2.0, -2.0, 0.0));
+        assert_eq!(a.clamp(low, high), Point3D::new(-1.0, 2.0, -2.0));
+    }
+
+    #[test]
+    fn view_matrix_follows_a_quarter_turn() {
+        // turned from looking down +z to looking down +x, which puts -z on
+        // the right
+        let camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(1.0, 0.0, 0.0));
+
+        assert!(camera.view_matrix().transform_point(Point3D::new(5.0, 0.0, 0.0)).approx_eq(Point3D::new(0.0, 0.0, 5.0), 1e-12));
+        assert!(camera.view_matrix().transform_point(Point3D::new(5.0, 0.0, -1.0)).approx_eq(Point3D::new(1.0, 0.0, 5.0), 1e-12));
+
+        let ahead = camera.screen_transform().transform_point(Point3D::new(5.0, 0.0, 0.0));
+        let right = camera.screen_transform().transform_point(Point3D::new(5.0, 0.0, -1.0));
+        assert!((ahead.x - 0.5).abs() < 1e-12 && (ahead.y - 0.5).abs() < 1e-12);
+        assert!(right.x > 0.5);
+
+        // what used to be straight ahead is now off to the side
+        assert_eq!(camera.depth_of(Point3D::new(0.0, 0.0, 5.0)), 0.0);
+    }
+
+    #[test]
+    fn hidden_line_hides_the_back_of_a_cube() {
+        // objects are moved by minus their position, see to_world
+        let mut cube = crate::primitives::cube(1.0);
+        cube.position = Point3D::new(0.0, 0.0, -3.0);
+
+        // with culling off only the depth test can hide the rear face's edges
+        let settings = RenderSettings { render_mode: RenderMode::HiddenLine, cull_back_faces: false, ..RenderSettings::default() };
+        let render = |object: &Object3D| {
+            let mut buffer = PaintBuffer::new(128, 128);
+            buffer.clear(settings.background);
+            object.paint_to_buffer(&mut buffer, test_scene(), settings, None);
+
+            buffer
+        };
+
+        // the left edges of the front face (at 2.5 deep, so 0.4 left of the
+        // center in ndc) and of the rear one (3.5 deep, 0.29) on the middle row
+        let (front_x, rear_x) = (38, 46);
+
+        let buffer = render(&cube);
+        assert_eq!(pixel(&buffer, front_x, 64), settings.wireframe_color);
+        assert_eq!(pixel(&buffer, rear_x, 64), settings.background);
+
+        // the rear face (+z) on its own has its edge there
+        let mut rear = Object3D::new(cube.triangles[8..10].to_vec());
+        rear.position = cube.position;
+        assert_eq!(pixel(&render(&rear), rear_x, 64), settings.wireframe_color);
+    }
+
+    #[test]
+    fn merge_bakes_in_each_transform() {
+        let mut moved = crate::primitives::cube(1.0);
+        moved.position = Point3D::new(1.0, 2.0, 3.0);
+        let mut turned = crate::primitives::cube(0.5);
+        turned.rotation = std::f64::consts::FRAC_PI_2;
+        turned.position = Point3D::new(-1.0, 0.0, 0.5);
+
+        let merged = Object3D::merge(&[moved, turned]);
+        let (moved, turned) = (crate::primitives::cube(1.0), crate::primitives::cube(0.5));
+        assert_eq!(merged.triangles.len(), moved.triangles.len() + turned.triangles.len());
+        assert_eq!((merged.position, merged.rotation), (Point3D::new(0.0, 0.0, 0.0), 0.0));
+
+        fn check(merged: &ColorTriangle, original: &ColorTriangle, position: fn(Point3D) -> Point3D, normal: fn(Point3D) -> Point3D) {
+            let corners = |tri: Triangle3D| [tri.a, tri.b, tri.c];
+
+            for (p, q) in corners(merged.tri).into_iter().zip(corners(original.tri)) {
+                assert!(p.approx_eq(position(q), 1e-12), "{:?} isn't {:?} moved", p, q);
+            }
+            for (n, m) in corners(merged.normal_tri).into_iter().zip(corners(original.normal_tri)) {
+                assert!(n.approx_eq(normal(m), 1e-12));
+            }
+        }
+
+        // objects are moved by minus their position, and a quarter turn takes
+        // (x, z) to (-z, x)
+        for (merged, original) in merged.triangles.iter().zip(&moved.triangles) {
+            check(merged, original, |p| Point3D::new(p.x - 1.0, p.y - 2.0, p.z - 3.0), |n| n);
+        }
+        for (merged, original) in merged.triangles[moved.triangles.len()..].iter().zip(&turned.triangles) {
+            check(merged, original, |p| Point3D::new(-p.z + 1.0, p.y, p.x - 0.5), |n| Point3D::new(-n.z, n.y, n.x));
+        }
+    }
+
+    #[test]
+    fn skybox_shows_the_face_looked_at() {
+        use crate::texture::CubeFace;
+
+        // a solid color per face, in CubeFace order
+        let colors = [0xFF0000, 0x00FFFF, 0x00FF00, 0xFF00FF, 0x0000FF, 0xFFFF00];
+        let cube_map = CubeMap::new(colors.map(|color| Texture::new(1, 1, vec![color])));
+
+        let views = [
+            (Point3D::new(1.0, 0.0, 0.0), CubeFace::PositiveX),
+            (Point3D::new(-1.0, 0.0, 0.0), CubeFace::NegativeX),
+            (Point3D::new(0.0, 1.0, 0.1), CubeFace::PositiveY),
+            (Point3D::new(0.0, -1.0, 0.1), CubeFace::NegativeY),
+            (Point3D::new(0.0, 0.0, 1.0), CubeFace::PositiveZ),
+            (Point3D::new(0.2, 0.1, -1.0), CubeFace::NegativeZ),
+        ];
+
+        for (view_dir, face) in views {
+            let camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), view_dir);
+            let mut buffer = PaintBuffer::new(64, 64);
+            render_skybox(&mut buffer, camera, RenderSettings::default(), &cube_map);
+
+            let ndc = Camera::screen_to_ndc(Point2D::new(32.5 / 64.0, 32.5 / 64.0));
+            assert_eq!(CubeMap::face_and_uv(camera.view_ray(ndc.x, ndc.y)).0, face);
+            assert_eq!(pixel(&buffer, 32, 32), colors[face as usize], "looking along {:?}", view_dir);
+        }
+
+        // off to the side of the view the next face over shows: looking
+        // halfway between +z and +x, the left half is +z and the right +x
+        let camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(1.0, 0.0, 1.0));
+        let mut buffer = PaintBuffer::new(64, 64);
+        render_skybox(&mut buffer, camera, RenderSettings::default(), &cube_map);
+
+        assert_eq!(pixel(&buffer, 8, 32), colors[CubeFace::PositiveZ as usize]);
+        assert_eq!(pixel(&buffer, 56, 32), colors[CubeFace::PositiveX as usize]);
+        assert!(buffer.z_buffer.iter().all(|&z| z == f64::MAX));
+    }
+
+    #[test]
+    fn vertical_gradient_runs_from_top_to_bottom() {
+        let (top, bottom) = (0x204080, 0x60C000);
+        let mut buffer = PaintBuffer::new(4, 5);
+        buffer.fill_vertical_gradient(top, bottom);
+
+        let row = |buffer: &PaintBuffer, y: u32| (0..4).map(|x| pixel(buffer, x, y)).collect::<Vec<u32>>();
+        assert_eq!(row(&buffer, 0), vec![top; 4]);
+        assert_eq!(row(&buffer, 4), vec![bottom; 4]);
+        assert_eq!(row(&buffer, 2), vec![0x408040; 4]);
+        assert_eq!(row(&buffer, 1), vec![0x306060; 4]);
+
+        // filling only some rows keeps them where they are in the whole span
+        let mut part = PaintBuffer::new(4, 5);
+        part.fill_vertical_gradient_pixels(0..4, 2..4, 0..5, top, bottom);
+        assert_eq!(row(&part, 0), vec![0; 4]);
+        assert_eq!(row(&part, 2), row(&buffer, 2));
+        assert_eq!(row(&part, 3), row(&buffer, 3));
+    }
+
+    #[test]
+    fn axes_end_in_distinct_places() {
+        let camera = Camera::new(Point3D::new(2.0, 1.5, -3.0), Point3D::new(-2.0, -1.5, 3.0));
+        let scene = Scene::new(camera, Light::new(Point3D::new(0.0, 0.0, -1.0), (1.0, 1.0, 1.0)));
+        let Scene(_, _, screen_transform) = scene;
+        let settings = RenderSettings::default();
+
+        let mut buffer = PaintBuffer::new(64, 64);
+        render_axes(&mut buffer, scene, settings, 1.0);
+
+        let to_pixel = |p: Point3D| {
+            let projected = screen_transform.transform_point(p);
+            let screen = settings.viewport.from_unit(Point2D::new(projected.x, projected.y));
+
+            ((screen.x * 64.0).round() as u32, (screen.y * 64.0).round() as u32)
+        };
+
+        let origin = to_pixel(Point3D::new(0.0, 0.0, 0.0));
+        let ends = [
+            (to_pixel(Point3D::new(1.0, 0.0, 0.0)), 0xFF0000),
+            (to_pixel(Point3D::new(0.0, 1.0, 0.0)), 0x00FF00),
+            (to_pixel(Point3D::new(0.0, 0.0, 1.0)), 0x0000FF),
+        ];
+
+        for (i, &(end, color)) in ends.iter().enumerate() {
+            assert_ne!(end, origin);
+            assert!(ends[i + 1..].iter().all(|&(other, _)| other != end), "two axes end at {:?}", end);
+            assert_eq!(pixel(&buffer, end.0, end.1), color);
+        }
+    }
+
+    #[test]
+    fn aa_line_in_a_band_matches_the_whole_frame() {
+        let (from, to) = (Point2D::new(0.1, 0.05), Point2D::new(0.9, 0.95));
+
+        let mut whole = PaintBuffer::new(32, 32);
+        whole.draw_line_aa(from, to, 0xFFFFFF);
+
+        // only rows 8 to 16, the rest of the line is cut off
+        let mut pixels = vec![0; 32 * 8];
+        let mut band = PaintBuffer::band(32, 32, 8..16, &mut pixels).unwrap();
+        assert_eq!(band.rows(), 8..16);
+        band.draw_line_aa(from, to, 0xFFFFFF);
+
+        assert_eq!(band.pixel_buffer.to_vec(), whole.pixel_buffer[whole.index_of(0, 8)..whole.index_of(0, 16)].to_vec());
+    }
 }
@@ -0,0 +1,621 @@
+use std::time::Duration;
+use crate::gbuffer::GBuffer;
+use crate::shadow::{render_shadow_map, ShadowMap};
+use crate::texture::CubeMap;
+use crate::triangles::*;
+
+// everything needed to render a frame, independent of any windowing code
+pub struct World {
+    pub camera: Camera,
+    pub light: Light,
+    pub objects: Vec<Object3D>,
+    // moves the light over time when set, otherwise it stays put
+    pub light_orbit: Option<LightOrbit>,
+    // drawn behind everything in place of the background color when set
+    pub skybox: Option<CubeMap>,
+    // the elapsed time of the last update, so the next one knows how much
+    // time passed since
+    last_update: Duration,
+    // what redraw_regions saw last time it was called
+    last_frame: Option<FrameState>,
+}
+
+// how long the lines RenderSettings::show_axes draws are
+pub const AXES_LENGTH: f64 = 1.0;
+
+// screen tiles are this many pixels square, see World::redraw_regions
+pub const DIRTY_TILE_SIZE: u32 = 32;
+
+// World::render_viewport doesn't give a thread fewer pixels than this to paint
+pub const MIN_PIXELS_PER_THREAD: usize = 128 * 128;
+
+// enough of a frame to tell what changed by the next one
+#[derive(Clone, Debug)]
+struct FrameState {
+    camera: Camera,
+    light: Light,
+    settings: RenderSettings,
+    size: (u32, u32),
+    objects: Vec<ObjectState>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ObjectState {
+    position: Point3D,
+    rotation: f64,
+    // (min x, min y, max x, max y) pixels it covered, max exclusive
+    bounds: Option<(u32, u32, u32, u32)>,
+}
+
+impl World {
+    pub fn new(camera: Camera, light: Light, objects: Vec<Object3D>) -> Self {
+        Self { camera, light, objects, light_orbit: None, skybox: None, last_update: Duration::ZERO, last_frame: None }
+    }
+
+    // advances the animation state to `elapsed` time since the start.
+    // objects turn by their rotation speed for however long it's been since
+    // the last update, so changing the speed never makes them jump
+    pub fn update(&mut self, elapsed: Duration) {
+        self.step(elapsed.saturating_sub(self.last_update));
+        self.last_update = elapsed;
+
+        if let Some(orbit) = self.light_orbit {
+            self.light.position = orbit.position_at(elapsed.as_secs_f64());
+        }
+    }
+
+    // turns every object by its rotation speed times dt
+    pub fn step(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f64();
+
+        for object in &mut self.objects {
+            object.rotation += object.rotation_speed * dt;
+        }
+    }
+
+    // clears the buffer (or rather, the settings' viewport of it) and paints
+    // every object into it
+    pub fn render(&self, buffer: &mut PaintBuffer, settings: RenderSettings) {
+        self.render_viewport(buffer, self.camera, settings.viewport, settings);
+    }
+
+    // clears a viewport's rect of the buffer and paints every object, as seen
+    // from `camera`, into just that rect. the rest of the buffer is left
+    // alone, so this can be called once per view for split screen rendering.
+    // the camera's aspect should match the rect's
+    pub fn render_viewport(&self, buffer: &mut PaintBuffer, camera: Camera, viewport: Viewport, settings: RenderSettings) {
+        let settings = RenderSettings { viewport, ..settings };
+        let (range_x, range_y) = settings.pixel_bounds(buffer.width, buffer.height);
+
+        let shadow_map = settings.shadows.then(|| {
+            render_shadow_map(&self.objects, self.light, settings.shadow_map_size, settings.shadow_bias, settings.front_face)
+        });
+
+        // small regions, like the ones render_regions redraws, aren't worth
+        // splitting between threads
+        let threads = usize::min(settings.thread_count(), range_y.len())
+            .min(usize::max(range_x.len() * range_y.len() / MIN_PIXELS_PER_THREAD, 1));
+        if threads <= 1 {
+            self.paint(buffer, camera, settings, shadow_map.as_ref());
+        } else {
+            self.paint_bands(buffer, camera, settings, shadow_map.as_ref(), threads);
+        }
+
+        // ssao runs on the finished pixels, looking up to its radius past the
+        // bounds (within the viewport) so that the edges of a scissored
+        // redraw come out the same as in a whole frame
+        if settings.ssao {
+            let radius = settings.ssao_radius;
+            let (view_x, view_y) = settings.viewport.rect.pixel_range(buffer.width, buffer.height);
+            let sample_x = u32::max(range_x.start.saturating_sub(radius), view_x.start)..u32::min(range_x.end + radius, view_x.end);
+            let sample_y = u32::max(range_y.start.saturating_sub(radius), view_y.start)..u32::min(range_y.end + radius, view_y.end);
+
+            buffer.apply_ssao_sampling(range_x, range_y, sample_x, sample_y, radius, settings.ssao_strength);
+        }
+    }
+
+    // paints the settings' pixel bounds split into bands of rows, one per
+    // thread. every thread paints its rows of the pixels in place, but
+    // depths (and hdr colors and overdraw counts) into band sized buffers of
+    // its own, which then get copied back
+    fn paint_bands(&self, buffer: &mut PaintBuffer, camera: Camera, settings: RenderSettings, shadow_map: Option<&ShadowMap>, threads: usize) {
+        let (range_x, range_y) = settings.pixel_bounds(buffer.width, buffer.height);
+        let (width, height) = (buffer.width, buffer.height);
+        let (hdr, overdraw) = (buffer.hdr_buffer.is_some(), buffer.overdraw.is_some());
+        let band_height = range_y.len().div_ceil(threads) as u32;
+
+        let rows = buffer.index_of(0, range_y.start)..buffer.index_of(0, range_y.end);
+        let pixels = &mut buffer.pixel_buffer[rows];
+
+        let painted = std::thread::scope(|scope| {
+            let handles = pixels.chunks_mut((band_height * width) as usize).enumerate().map(|(i, band_pixels)| {
+                let start = range_y.start + i as u32 * band_height;
+                let band = start..start + band_pixels.len() as u32 / width;
+                let band_settings = RenderSettings {
+                    scissor: Some((range_x.start, band.start, range_x.len() as u32, band.len() as u32)),
+                    ..settings
+                };
+
+                scope.spawn(move || {
+                    let mut band_buffer = PaintBuffer::band(width, height, band, band_pixels).unwrap();
+                    if hdr {
+                        band_buffer.enable_hdr();
+                    }
+                    if overdraw {
+                        band_buffer.enable_overdraw();
+                    }
+
+                    self.paint(&mut band_buffer, camera, band_settings, shadow_map);
+                    (band_buffer.first_row, band_buffer.z_buffer, band_buffer.hdr_buffer, band_buffer.overdraw)
+                })
+            }).collect::<Vec<_>>();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        for (first_row, z_buffer, band_hdr, band_overdraw) in painted {
+            let band_rows = z_buffer.len() as u32 / width;
+
+            for y in first_row..first_row + band_rows {
+                let row = buffer.index_of(range_x.start, y)..buffer.index_of(range_x.end, y);
+                let band_row = ((y - first_row) * width + range_x.start) as usize..((y - first_row) * width + range_x.end) as usize;
+
+                buffer.z_buffer[row.clone()].copy_from_slice(&z_buffer[band_row.clone()]);
+
+                if let (Some(hdr_buffer), Some(band_hdr)) = (&mut buffer.hdr_buffer, &band_hdr) {
+                    hdr_buffer[row.clone()].copy_from_slice(&band_hdr[band_row.clone()]);
+                }
+                if let (Some(overdraw), Some(band_overdraw)) = (&mut buffer.overdraw, &band_overdraw) {
+                    overdraw[row].copy_from_slice(&band_overdraw[band_row]);
+                }
+            }
+        }
+    }
+
+    // clears the settings' pixel bounds of the buffer and paints every
+    // object into them, all on the calling thread. ssao is left to
+    // render_viewport
+    fn paint(&self, buffer: &mut PaintBuffer, camera: Camera, settings: RenderSettings, shadow_map: Option<&ShadowMap>) {
+        let (range_x, range_y) = settings.pixel_bounds(buffer.width, buffer.height);
+        buffer.clear_pixels(range_x.clone(), range_y.clone(), settings.background);
+
+        // the gradient spans the whole viewport, however little of it is
+        // being drawn
+        if let Some((top, bottom)) = settings.background_gradient {
+            let (_, rows) = settings.viewport.rect.pixel_range(buffer.width, buffer.height);
+            buffer.fill_vertical_gradient_pixels(range_x.clone(), range_y.clone(), rows, top, bottom);
+        }
+
+        if let Some(skybox) = &self.skybox {
+            render_skybox(buffer, camera, settings, skybox);
+        }
+
+        let scene = Scene::new(camera, self.light);
+
+        for object in &self.objects {
+            object.paint_to_buffer(buffer, scene, settings, shadow_map);
+        }
+
+        if settings.show_axes {
+            render_axes(buffer, scene, settings, AXES_LENGTH);
+        }
+    }
+
+    // fills the settings' pixel bounds of a G-buffer with what the camera
+    // sees there instead of shading it, see GBuffer
+    pub fn render_gbuffer(&self, gbuffer: &mut GBuffer, settings: RenderSettings) {
+        let (range_x, range_y) = settings.pixel_bounds(gbuffer.width, gbuffer.height);
+        gbuffer.clear_pixels(range_x, range_y);
+
+        let scene = Scene::new(self.camera, self.light);
+
+        for (object_index, object) in self.objects.iter().enumerate() {
+            object.paint_to_gbuffer(gbuffer, scene, settings, object_index);
+        }
+    }
+
+    // draws a `thickness` pixel wide line of `color` around the visible
+    // silhouette of one of the objects, e.g. to highlight a selection. the
+    // object's own pixels are left alone, only the ones just outside of it
+    // are painted, on top of whatever is there
+    pub fn render_outline(&self, buffer: &mut PaintBuffer, settings: RenderSettings, object_index: usize, color: u32, thickness: u32) {
+        let mut gbuffer = GBuffer::new(buffer.width, buffer.height);
+        self.render_gbuffer(&mut gbuffer, settings);
+
+        let covered = |x: u32, y: u32| gbuffer.id_at(x, y).is_some_and(|(object, _)| object == object_index);
+        let (range_x, range_y) = settings.pixel_bounds(buffer.width, buffer.height);
+        let reach = thickness as i64;
+
+        // a band only gets the part of the outline that falls in its rows,
+        // though the object is looked for in the whole frame
+        let rows = buffer.rows();
+        let range_y = u32::max(range_y.start, rows.start)..u32::min(range_y.end, rows.end);
+
+        for y in range_y {
+            for x in range_x.clone() {
+                if covered(x, y) {
+                    continue;
+                }
+
+                // any of the object's pixels within a circle of the thickness
+                let near_object = (-reach..=reach).any(|dy| (-reach..=reach).any(|dx| {
+                    let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+
+                    dx * dx + dy * dy <= reach * reach
+                        && nx >= 0 && ny >= 0
+                        && covered(nx as u32, ny as u32)
+                }));
+
+                if near_object {
+                    let index = buffer.index_of(x, y);
+                    buffer.pixel_buffer[index] = color;
+                }
+            }
+        }
+    }
+
+    // the parts of a width x height buffer that need to be drawn again since
+    // the last call, as (x, y, width, height) scissor rects. everything is
+    // dirty on the first call, or when the camera, light, settings or size
+    // changed. otherwise only the tiles under objects that moved or turned
+    // are, both where they were and where they are now. shadows can fall
+    // anywhere, so with them on any movement redraws everything.
+    //
+    // objects are only compared by their position and rotation, call
+    // invalidate after changing anything else about them
+    pub fn redraw_regions(&mut self, width: u32, height: u32, settings: RenderSettings) -> Vec<(u32, u32, u32, u32)> {
+        let (range_x, range_y) = settings.pixel_bounds(width, height);
+        let everything = vec![(range_x.start, range_y.start, range_x.len() as u32, range_y.len() as u32)];
+
+        let objects: Vec<ObjectState> = self.objects.iter().map(|object| ObjectState {
+            position: object.position,
+            rotation: object.rotation,
+            bounds: self.pixel_bounds_of(object, width, height, settings),
+        }).collect();
+
+        let frame = FrameState { camera: self.camera, light: self.light, settings, size: (width, height), objects };
+        let Some(last) = self.last_frame.replace(frame.clone()) else {
+            return everything;
+        };
+
+        if last.camera != frame.camera || last.light != frame.light || last.settings != frame.settings || last.size != frame.size || last.objects.len() != frame.objects.len() {
+            return everything;
+        }
+
+        let changed: Vec<_> = last.objects.iter().zip(&frame.objects).filter(|(before, after)| before != after).collect();
+        if changed.is_empty() {
+            return Vec::new();
+        }
+
+        if settings.shadows {
+            return everything;
+        }
+
+        let tiles_x = width.div_ceil(DIRTY_TILE_SIZE);
+        let tiles_y = height.div_ceil(DIRTY_TILE_SIZE);
+        let mut dirty = vec![false; (tiles_x * tiles_y) as usize];
+
+        for bounds in changed.iter().flat_map(|(before, after)| [before.bounds, after.bounds]).flatten() {
+            let (min_x, min_y, max_x, max_y) = bounds;
+
+            for tile_y in min_y / DIRTY_TILE_SIZE..max_y.div_ceil(DIRTY_TILE_SIZE) {
+                for tile_x in min_x / DIRTY_TILE_SIZE..max_x.div_ceil(DIRTY_TILE_SIZE) {
+                    dirty[(tile_x + tile_y * tiles_x) as usize] = true;
+                }
+            }
+        }
+
+        // runs of dirty tiles along each row become one rect, cut down to
+        // the part of the buffer that gets drawn to
+        let mut regions = Vec::new();
+
+        for tile_y in 0..tiles_y {
+            let mut tile_x = 0;
+
+            while tile_x < tiles_x {
+                if !dirty[(tile_x + tile_y * tiles_x) as usize] {
+                    tile_x += 1;
+                    continue;
+                }
+
+                let start = tile_x;
+                while tile_x < tiles_x && dirty[(tile_x + tile_y * tiles_x) as usize] {
+                    tile_x += 1;
+                }
+
+                let x0 = u32::max(start * DIRTY_TILE_SIZE, range_x.start);
+                let y0 = u32::max(tile_y * DIRTY_TILE_SIZE, range_y.start);
+                let x1 = u32::min(tile_x * DIRTY_TILE_SIZE, range_x.end);
+                let y1 = u32::min((tile_y + 1) * DIRTY_TILE_SIZE, range_y.end);
+
+                if x0 < x1 && y0 < y1 {
+                    regions.push((x0, y0, x1 - x0, y1 - y0));
+                }
+            }
+        }
+
+        regions
+    }
+
+    // makes the next redraw_regions call report everything as dirty
+    pub fn invalidate(&mut self) {
+        self.last_frame = None;
+    }
+
+    // redraws just the given regions of a buffer holding the previous frame,
+    // leaving the rest of it as it was. see redraw_regions
+    pub fn render_regions(&self, buffer: &mut PaintBuffer, settings: RenderSettings, regions: &[(u32, u32, u32, u32)]) {
+        for &region in regions {
+            self.render(buffer, RenderSettings { scissor: Some(region), ..settings });
+        }
+    }
+
+    // the pixels an object covers in a width x height frame, padded by a
+    // pixel for edges and by the ssao radius since occlusion reaches that
+    // far. anything crossing the near plane can't be projected, so it is
+    // assumed to cover the whole frame
+    fn pixel_bounds_of(&self, object: &Object3D, width: u32, height: u32, settings: RenderSettings) -> Option<(u32, u32, u32, u32)> {
+        let (range_x, range_y) = settings.pixel_bounds(width, height);
+        let screen_transform = self.camera.screen_transform();
+
+        let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+        let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+
+        for tri in object.world_triangles() {
+            for p in [tri.tri.a, tri.tri.b, tri.tri.c] {
+                if self.camera.depth_of(p) <= f64::max(self.camera.near, 0.0) {
+                    return Some((range_x.start, range_y.start, range_x.end, range_y.end));
+                }
+
+                let projected = screen_transform.transform_point(p);
+                let screen = settings.viewport.from_unit(Point2D::new(projected.x, projected.y));
+
+                min_x = f64::min(min_x, screen.x * width as f64);
+                min_y = f64::min(min_y, screen.y * height as f64);
+                max_x = f64::max(max_x, screen.x * width as f64);
+                max_y = f64::max(max_y, screen.y * height as f64);
+            }
+        }
+
+        let padding = 1.0 + if settings.ssao { settings.ssao_radius as f64 } else { 0.0 };
+        let clamp_x = |v: f64| f64::clamp(v, range_x.start as f64, range_x.end as f64) as u32;
+        let clamp_y = |v: f64| f64::clamp(v, range_y.start as f64, range_y.end as f64) as u32;
+
+        let bounds = (
+            clamp_x((min_x - padding).floor()),
+            clamp_y((min_y - padding).floor()),
+            clamp_x((max_x + padding).ceil()),
+            clamp_y((max_y + padding).ceil()),
+        );
+
+        (bounds.0 < bounds.2 && bounds.1 < bounds.3).then_some(bounds)
+    }
+
+    // finds the nearest triangle under a point on the screen, where (0, 0) is
+    // the top left and (1, 1) the bottom right. returns the index of the
+    // object and of the triangle within it
+    pub fn pick(&self, screen_x: f64, screen_y: f64) -> Option<(usize, usize)> {
+        let ndc = Camera::screen_to_ndc(Point2D::new(screen_x, screen_y));
+        let dir = self.camera.view_ray(ndc.x, ndc.y);
+
+        let mut nearest: Option<(f64, usize, usize)> = None;
+
+        for (object_index, object) in self.objects.iter().enumerate() {
+            for (tri_index, tri) in object.world_triangles().enumerate() {
+                let Some(t) = tri.tri.intersect_ray(self.camera.position, dir) else {
+                    continue;
+                };
+
+                if nearest.is_none_or(|(nearest_t, _, _)| t < nearest_t) {
+                    nearest = Some((t, object_index, tri_index));
+                }
+            }
+        }
+
+        nearest.map(|(_, object_index, tri_index)| (object_index, tri_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::cube;
+
+    fn test_world(objects: Vec<Object3D>) -> World {
+        let camera = Camera::new(Point3D::new(0.0, 0.0, -3.0), Point3D::new(0.0, 0.0, 1.0));
+        let light = Light::new(Point3D::new(2.0, 0.75, -2.0), (1.0, 1.0, 1.0));
+
+        World::new(camera, light, objects)
+    }
+
+    #[test]
+    fn update_turns_by_elapsed_time() {
+        let mut world = test_world(vec![cube(1.0)]);
+
+        for ms in [250, 500, 750, 1000] {
+            world.update(Duration::from_millis(ms));
+        }
+
+        assert!((world.objects[0].rotation - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn render_viewport_leaves_the_rest_alone() {
+        let world = test_world(vec![cube(1.0)]);
+        let mut buffer = PaintBuffer::new(64, 32);
+        buffer.clear(0x123456);
+
+        let left = Viewport::new(Rect::new(Point2D::new(0.0, 0.0), Point2D::new(0.5, 1.0)));
+        world.render_viewport(&mut buffer, world.camera, left, RenderSettings::default());
+
+        for y in 0..32 {
+            for x in 32..64 {
+                let index = (x + y * 64) as usize;
+                assert_eq!(buffer.pixel_buffer[index], 0x123456, "pixel ({}, {}) was drawn to", x, y);
+                assert_eq!(buffer.z_buffer[index], f64::MAX);
+            }
+        }
+
+        assert!(buffer.pixel_buffer[..32].iter().all(|&pixel| pixel != 0x123456));
+        assert_ne!(buffer.pixel_buffer[16 + 16 * 64], RenderSettings::default().background);
+    }
+
+    #[test]
+    fn update_turns_by_speed_times_elapsed() {
+        let mut world = test_world(vec![cube(1.0), cube(1.0)]);
+        world.objects[0].rotation_speed = 2.0;
+        world.objects[1].rotation_speed = -0.5;
+
+        world.update(Duration::from_millis(250));
+        world.update(Duration::from_millis(1000));
+        assert!((world.objects[0].rotation - 2.0).abs() < 1e-12);
+        assert!((world.objects[1].rotation + 0.5).abs() < 1e-12);
+
+        world.step(Duration::from_millis(500));
+        assert!((world.objects[0].rotation - 3.0).abs() < 1e-12);
+    }
+
+    // mean column of the pixels that aren't background
+    fn painted_center_x(buffer: &PaintBuffer, background: u32) -> f64 {
+        let columns = (0..buffer.pixel_buffer.len())
+            .filter(|&index| buffer.pixel_buffer[index] != background)
+            .map(|index| (index as u32 % buffer.width) as f64)
+            .collect::<Vec<f64>>();
+
+        columns.iter().sum::<f64>() / columns.len() as f64
+    }
+
+    #[test]
+    fn looking_left_moves_the_object_right() {
+        let settings = RenderSettings::default();
+        let mut world = test_world(vec![cube(1.0)]);
+
+        let mut buffer = PaintBuffer::new(64, 64);
+        world.render(&mut buffer, settings);
+        assert!((painted_center_x(&buffer, settings.background) - 31.5).abs() < 1.0);
+
+        world.camera.set_view_dir(Point3D::new(-0.3, 0.0, 1.0));
+        world.render(&mut buffer, settings);
+        let center_x = painted_center_x(&buffer, settings.background);
+        assert!(center_x > 40.0);
+
+        // and picking finds it where it's drawn
+        assert!(world.pick(0.5, 0.5).is_none());
+        assert!(world.pick((center_x + 0.5) / 64.0, 0.5).is_some());
+    }
+
+    #[test]
+    fn static_scene_has_nothing_to_redraw() {
+        let settings = RenderSettings::default();
+        let mut world = test_world(vec![cube(1.0)]);
+
+        assert_eq!(world.redraw_regions(64, 64, settings), vec![(0, 0, 64, 64)]);
+        assert!(world.redraw_regions(64, 64, settings).is_empty());
+    }
+
+    #[test]
+    fn ssao_regions_match_a_whole_frame() {
+        // inside a box looking into a corner, where the walls meet in creases
+        let mut world = test_world(vec![cube(4.0)]);
+        world.camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(1.0, 0.8, 1.2));
+        let settings = RenderSettings { ssao: true, cull_back_faces: false, ..RenderSettings::default() };
+
+        let mut whole = PaintBuffer::new(64, 64);
+        world.render(&mut whole, settings);
+
+        let mut plain = PaintBuffer::new(64, 64);
+        world.render(&mut plain, RenderSettings { ssao: false, ..settings });
+        assert_ne!(whole.pixel_buffer.to_vec(), plain.pixel_buffer.to_vec());
+
+        let mut regions = PaintBuffer::new(64, 64);
+        world.render(&mut regions, settings);
+        world.render_regions(&mut regions, settings, &[(0, 0, 32, 32), (32, 16, 32, 24), (8, 40, 20, 20)]);
+
+        assert_eq!(regions.pixel_buffer.to_vec(), whole.pixel_buffer.to_vec());
+    }
+
+    #[test]
+    fn threaded_render_matches_one_thread() {
+        let mut world = test_world(vec![cube(1.0), cube(0.5)]);
+        world.objects[0].rotation = 0.6;
+        world.objects[1].position = Point3D::new(-0.8, 0.3, 0.5);
+
+        let render = |threads: usize| {
+            let settings = RenderSettings { threads: Some(threads), ssao: true, background_gradient: Some((0x203040, 0x000000)), ..RenderSettings::default() };
+            let mut buffer = PaintBuffer::new(320, 240);
+            buffer.enable_hdr();
+            buffer.enable_overdraw();
+            world.render(&mut buffer, settings);
+
+            buffer
+        };
+
+        let (one, four) = (render(1), render(4));
+        assert_eq!(four.pixel_buffer.to_vec(), one.pixel_buffer.to_vec());
+        assert_eq!(four.z_buffer, one.z_buffer);
+        assert_eq!(four.hdr_buffer, one.hdr_buffer);
+        assert_eq!(four.overdraw, one.overdraw);
+    }
+
+    #[test]
+    fn outline_hugs_the_object_from_outside() {
+        let mut world = test_world(vec![cube(1.0), cube(0.5)]);
+        world.objects[0].rotation = 0.4;
+        world.objects[1].position = Point3D::new(1.2, 0.0, 0.0);
+        let settings = RenderSettings::default();
+        let (color, thickness) = (0xFF00FF, 2);
+
+        let mut plain = PaintBuffer::new(64, 64);
+        world.render(&mut plain, settings);
+        let mut outlined = PaintBuffer::new(64, 64);
+        world.render(&mut outlined, settings);
+        world.render_outline(&mut outlined, settings, 0, color, thickness);
+
+        let mut gbuffer = GBuffer::new(64, 64);
+        world.render_gbuffer(&mut gbuffer, settings);
+        let covered = |x: i64, y: i64| (0..64).contains(&x) && (0..64).contains(&y) && gbuffer.id_at(x as u32, y as u32).is_some_and(|(object, _)| object == 0);
+        // to the nearest of the object's pixels, if any are within reach
+        let reach = thickness as i64;
+        let distance_squared = |x: i64, y: i64| (-reach..=reach)
+            .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| covered(x + dx, y + dy))
+            .map(|(dx, dy)| dx * dx + dy * dy)
+            .min();
+
+        let mut outline_pixels = 0;
+        for y in 0..64 {
+            for x in 0..64 {
+                let index = (x + y * 64) as usize;
+
+                if covered(x, y) {
+                    assert_eq!(outlined.pixel_buffer[index], plain.pixel_buffer[index], "({}, {}) is on the object", x, y);
+                } else if distance_squared(x, y).is_some_and(|d| d <= reach * reach) {
+                    assert_eq!(outlined.pixel_buffer[index], color, "({}, {}) is next to the object", x, y);
+                    outline_pixels += 1;
+                } else {
+                    assert_eq!(outlined.pixel_buffer[index], plain.pixel_buffer[index], "({}, {}) is away from the object", x, y);
+                }
+            }
+        }
+
+        assert!(outline_pixels > 0);
+    }
+
+    #[test]
+    fn outline_in_a_band_matches_the_whole_frame() {
+        let world = test_world(vec![cube(1.0)]);
+        let settings = RenderSettings::default();
+
+        let mut whole = PaintBuffer::new(64, 64);
+        world.render_outline(&mut whole, settings, 0, 0xFF00FF, 2);
+
+        // rows 16 to 24 cross the top of the cube's outline
+        let mut pixels = vec![0; 64 * 8];
+        let mut band = PaintBuffer::band(64, 64, 16..24, &mut pixels).unwrap();
+        world.render_outline(&mut band, settings, 0, 0xFF00FF, 2);
+
+        let rows = whole.index_of(0, 16)..whole.index_of(0, 24);
+        assert!(whole.pixel_buffer[rows.clone()].contains(&0xFF00FF));
+        assert_eq!(band.pixel_buffer.to_vec(), whole.pixel_buffer[rows].to_vec());
+    }
+}
@@ -0,0 +1,55 @@
+// renders the dragon at rotation 0 and compares it against the checked-in
+// reference image. after a change that's meant to alter the output,
+// regenerate the reference with `UPDATE_GOLDEN=1 cargo test --test golden`
+use rust_graphics::loader::load_obj;
+use rust_graphics::renderer::render_headless;
+use rust_graphics::triangles::*;
+use rust_graphics::world::World;
+
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 300;
+
+// per channel difference allowed before a pixel counts as changed, and how
+// many changed pixels are still a match. leaves room for floating point
+// differences between platforms
+const TOLERANCE: u8 = 2;
+const MAX_DIFFERING: usize = 60;
+
+fn path(name: &str) -> String {
+    format!("{}/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+// the low-poly dragon with the same camera and light as the demo
+fn render_golden() -> PaintBuffer<'static> {
+    let object = load_obj(path("res/dragon_lowpoly.obj")).unwrap();
+
+    let mut camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0));
+    camera.frame_object(&object, camera.fov_y_radians, camera.aspect);
+    let light = Light::new(Point3D::new(2.0, 0.75, -0.5), (1.0, 0.3, 0.0));
+    let world = World::new(camera, light, vec![object]);
+
+    render_headless(&world, RenderSettings::default(), WIDTH, HEIGHT)
+}
+
+#[test]
+fn dragon_matches_golden_image() {
+    let golden_path = path("res/golden/dragon.png");
+    let buffer = render_golden();
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some_and(|update| update == "1") {
+        buffer.save_png(&golden_path).unwrap();
+        return;
+    }
+
+    let reference = image::open(&golden_path).unwrap().to_rgba8();
+    assert_eq!(reference.dimensions(), (WIDTH, HEIGHT), "golden image has the wrong size");
+
+    let differing = buffer
+        .to_rgba_image()
+        .pixels()
+        .zip(reference.pixels())
+        .filter(|(a, b)| (0..3).any(|i| a[i].abs_diff(b[i]) > TOLERANCE))
+        .count();
+
+    assert!(differing <= MAX_DIFFERING, "golden image drifted: {} pixels differ", differing);
+}
@@ -0,0 +1,22 @@
+// the rasterizer on its own, without the window feature. run with
+// `cargo test --no-default-features --test headless`
+use rust_graphics::primitives::cube;
+use rust_graphics::renderer::render_headless;
+use rust_graphics::triangles::*;
+use rust_graphics::world::World;
+
+#[test]
+fn renders_without_a_window() {
+    let object = cube(1.0);
+    let mut camera = Camera::new(Point3D::new(0.0, 0.0, 0.0), Point3D::new(0.0, 0.0, 1.0));
+    camera.frame_object(&object, camera.fov_y_radians, camera.aspect);
+    let light = Light::new(Point3D::new(2.0, 0.75, -2.0), (1.0, 1.0, 1.0));
+    let world = World::new(camera, light, vec![object]);
+
+    let settings = RenderSettings::default();
+    let buffer = render_headless(&world, settings, 80, 60);
+
+    assert_eq!(buffer.pixel_buffer.len(), 80 * 60);
+    assert!(buffer.pixel_buffer.iter().any(|&pixel| pixel != settings.background));
+    assert_eq!(buffer.to_rgba_image().dimensions(), (80, 60));
+}